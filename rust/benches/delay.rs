@@ -0,0 +1,184 @@
+// -------------------------------------------------------------------
+// Performance comparison of the Rust and the C delay.
+//
+// Both shared objects are loaded the way a host would do it and their
+// run() functions are timed directly, without any file I/O or process
+// startup involved. For each combination of sample rate and block size
+// the time per sample is reported as the mean over a number of
+// repetitions together with its 95% confidence interval.
+//
+// The C delay is a plain delay line without any of the features of the
+// Rust one. The Rust delay is measured twice, once set up to process
+// the audio exactly like the C delay and once with all controls at
+// their defaults, i.e. with interpolation and smoothing. Run it using
+//
+//     (cd ../c && make) && cargo bench
+//
+// The locations of the plugins can be overridden with the environment
+// variables RUST_DELAY_PLUGIN and C_DELAY_PLUGIN.
+// -------------------------------------------------------------------
+
+extern crate ladspa;
+extern crate rust_delay_5s_stereo;
+
+use ladspa::Data;
+use ladspa_host::{Library, Plugin, PortKind};
+use std::env;
+use std::path::PathBuf;
+use std::time::Instant;
+
+// -------------------------------------------------------------------
+
+const SAMPLE_RATES: [u64; 3] = [44100, 48000, 96000];
+const BLOCK_SIZES: [usize; 5] = [16, 64, 256, 1024, 4096];
+
+// Each repetition processes this amount of audio.
+const SECONDS_PER_REPETITION: usize = 1;
+const REPETITIONS: usize = 30;
+
+// 97.5% quantile of Student's t-distribution with REPETITIONS - 1
+// degrees of freedom.
+const T_QUANTILE: f64 = 2.045;
+
+// -------------------------------------------------------------------
+
+struct Candidate {
+    name: &'static str,
+    path: PathBuf,
+    label: &'static str,
+    // Control values differing from the defaults.
+    controls: &'static [(&'static str, Data)],
+}
+
+// -------------------------------------------------------------------
+
+fn candidates() -> Vec<Candidate> {
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let path = |variable: &str, default: PathBuf| env::var_os(variable)
+	.map(PathBuf::from)
+	.unwrap_or(default);
+    
+    vec![
+	Candidate {
+	    name: "c",
+	    path: path("C_DELAY_PLUGIN", manifest.join("../c/delay_stereo.so")),
+	    label: "c_delay_5s_stereo",
+	    controls: &[("Delay (Seconds) (Left)", 0.1),
+			("Delay (Seconds) (Right)", 1.0),
+			("Dry/Wet Balance (Left)", 0.5),
+			("Dry/Wet Balance (Right)", 1.0)],
+	},
+	Candidate {
+	    name: "rust",
+	    path: path("RUST_DELAY_PLUGIN",
+		       deps.join("librust_delay_5s_stereo.so")),
+	    label: "rust_delay_5s_stereo",
+	    controls: &[("Left Delay (seconds)", 0.1),
+			("Right Delay (seconds)", 1.0),
+			("Left Dry/Wet", 0.5),
+			("Right Dry/Wet", 1.0),
+			("Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)", 0.0),
+			("Smoothing Time (seconds)", 0.0)],
+	},
+	Candidate {
+	    name: "rust (defaults)",
+	    path: path("RUST_DELAY_PLUGIN",
+		       deps.join("librust_delay_5s_stereo.so")),
+	    label: "rust_delay_5s_stereo",
+	    controls: &[("Left Delay (seconds)", 0.1),
+			("Right Delay (seconds)", 1.0),
+			("Left Dry/Wet", 0.5),
+			("Right Dry/Wet", 1.0)],
+	},
+    ]
+}
+
+// -------------------------------------------------------------------
+
+// Mean and half width of the 95% confidence interval.
+fn confidence_interval(values: &[f64]) -> (f64, f64) {
+    let count = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter()
+	.map(|x| (x - mean) * (x - mean))
+	.sum::<f64>() / (count - 1.0);
+    
+    (mean, T_QUANTILE * (variance / count).sqrt())
+}
+
+// -------------------------------------------------------------------
+
+// Time per sample (in ns) of each repetition.
+fn measure(plugin: &Plugin, controls: &[Data], sample_rate: u64,
+	   block_size: usize) -> Vec<f64> {
+    let ports = plugin.ports();
+    let mut audio: Vec<Vec<Data>> = ports.iter()
+	.map(|port| (0..block_size)
+	     .map(|ii| (ii as Data * 0.05 + port.index as Data).sin())
+	     .collect())
+	.collect();
+    let mut controls = controls.to_vec();
+    let mut instance = plugin.instantiate(sample_rate).unwrap();
+    
+    unsafe {
+	let mut control = controls.iter_mut();
+	for (port, buffer) in ports.iter().zip(audio.iter_mut()) {
+	    match port.kind {
+		Some(PortKind::ControlInput) =>
+		    instance.connect_port(port.index, control.next().unwrap()),
+		_ => instance.connect_port(port.index, buffer.as_mut_ptr()),
+	    }
+	}
+    }
+    instance.activate();
+    
+    let blocks = SECONDS_PER_REPETITION * sample_rate as usize / block_size;
+    
+    (0..REPETITIONS)
+	.map(|_| {
+	    let start = Instant::now();
+	    for _ in 0..blocks {
+		// All ports are connected to the buffers above, which
+		// hold a whole block.
+		unsafe { instance.run(block_size) };
+	    }
+	    start.elapsed().as_nanos() as f64 / (blocks * block_size) as f64
+	})
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+fn main() {
+    let mut libraries = Vec::new();
+    
+    // Both candidates are LADSPA plugins built from this repository.
+    for candidate in candidates() {
+	match unsafe { Library::load(&candidate.path) } {
+	    Ok(library) => libraries.push((candidate, library)),
+	    Err(err) => eprintln!("skipping {} ({}): {}", candidate.name,
+				  candidate.path.display(), err),
+	}
+    }
+    
+    println!("{:>15} {:>11} {:>6} {:>22}", "plugin", "sample rate",
+	     "block", "ns/sample (95% CI)");
+    
+    for &sample_rate in SAMPLE_RATES.iter() {
+	for &block_size in BLOCK_SIZES.iter() {
+	    for (candidate, library) in libraries.iter() {
+		let plugin = library.plugin(candidate.label).unwrap();
+		let controls = plugin.controls(sample_rate, candidate.controls)
+		    .unwrap();
+		let (mean, error) = confidence_interval(
+		    &measure(&plugin, &controls, sample_rate, block_size));
+		
+		println!("{:>15} {:>11} {:>6} {:>12.3} ± {:<7.3}", candidate.name,
+			 sample_rate, block_size, mean, error);
+	    }
+	}
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,250 @@
+// -------------------------------------------------------------------
+// Offline rendering of audio files through LADSPA plugins.
+//
+// Loads any LADSPA library, lists the plugins it contains or renders
+// a WAV file through one of them. Control values are set by port
+// name; all remaining controls use their default values.
+// -------------------------------------------------------------------
+
+extern crate hound;
+extern crate ladspa;
+extern crate ladspa_host;
+
+use ladspa::Data;
+use ladspa_host::{Library, Plugin, PortKind};
+use std::error::Error;
+use std::path::Path;
+use std::{env, process};
+
+// -------------------------------------------------------------------
+
+const USAGE: &str = "\
+Usage:
+    ladspa-render list <library>
+    ladspa-render render <library> <label> <input.wav> <output.wav>
+		  [--block-size <samples>] [--tail <seconds>]
+		  [<port name>=<value> ...]
+
+Options:
+    --block-size <samples>  Samples passed to each call of run() [default: 1024]
+    --tail <seconds>        Silence appended to the input so the effect
+			    is able to ring out [default: 0]
+
+Example:
+    ladspa-render render target/release/librust_delay_5s_stereo.so \\
+	rust_delay_5s_stereo snare.wav out.wav \\
+	'Left Delay (seconds)=0.1' 'Left Dry/Wet=0.5'";
+
+const DEFAULT_BLOCK_SIZE: usize = 1024;
+
+// -------------------------------------------------------------------
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    
+    let result = match args.first().map(|command| command.as_str()) {
+	Some("list") if args.len() == 2 => list(&args[1]),
+	Some("render") if args.len() >= 5 => render(&args[1..]),
+	Some("-h") | Some("--help") => {
+	    println!("{}", USAGE);
+	    Ok(())
+	},
+	_ => {
+	    eprintln!("{}", USAGE);
+	    process::exit(2);
+	},
+    };
+    
+    if let Err(err) = result {
+	eprintln!("ladspa-render: {}", err);
+	process::exit(1);
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Relative paths are resolved against the working directory instead
+// of the search path of the dynamic linker. Like any other host, the
+// tool has to trust the user to name a LADSPA library.
+fn load_library(path: &str) -> Result<Library, ladspa_host::Error> {
+    let path = Path::new(path);
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    
+    unsafe { Library::load(path) }
+}
+
+// -------------------------------------------------------------------
+
+fn list(library: &str) -> Result<(), Box<dyn Error>> {
+    let library = load_library(library)?;
+    
+    for plugin in library.plugins() {
+	print_plugin(&plugin);
+    }
+    
+    Ok(())
+}
+
+// -------------------------------------------------------------------
+
+fn print_plugin(plugin: &Plugin) {
+    println!("{} ({}): {} by {}", plugin.label(), plugin.unique_id(),
+	     plugin.name(), plugin.maker());
+    
+    for port in plugin.ports() {
+	let kind = match port.kind {
+	    Some(PortKind::AudioInput) => "audio input",
+	    Some(PortKind::AudioOutput) => "audio output",
+	    Some(PortKind::ControlInput) => "control input",
+	    Some(PortKind::ControlOutput) => "control output",
+	    None => "invalid",
+	};
+	let bound = |bound: Option<Data>| bound
+	    .map(|x| x.to_string())
+	    .unwrap_or_else(|| "-".to_string());
+	
+	print!("    {:>3}  {:<15} {}", port.index, kind, port.name);
+	if port.kind == Some(PortKind::ControlInput) {
+	    print!("  [{}, {}]", bound(port.lower_bound(44100)),
+		   bound(port.upper_bound(44100)));
+	    if let Some(default) = port.default_value(44100) {
+		print!(" default {}", default);
+	    }
+	}
+	println!();
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn render(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let library = load_library(&args[0])?;
+    let plugin = library.plugin(&args[1])?;
+    let (input_path, output_path) = (&args[2], &args[3]);
+    
+    // ---------------------------------------------------------------
+    
+    let mut block_size = DEFAULT_BLOCK_SIZE;
+    let mut tail: Data = 0.0;
+    let mut changes: Vec<(&str, Data)> = Vec::new();
+    let mut options = args[4..].iter();
+    
+    while let Some(option) = options.next() {
+	let mut value = |name: &str| options.next()
+	    .ok_or_else(|| format!("missing value for {}", name));
+	
+	match option.as_str() {
+	    "--block-size" => block_size = value(option)?.parse()?,
+	    "--tail" => tail = value(option)?.parse()?,
+	    _ => {
+		let split = option.rfind('=')
+		    .ok_or_else(|| format!("unknown argument '{}'", option))?;
+		changes.push((&option[..split], option[split + 1..].parse()?));
+	    },
+	}
+    }
+    
+    if block_size == 0 {
+	return Err("the block size must be positive".into());
+    }
+    
+    // ---------------------------------------------------------------
+    
+    let mut reader = hound::WavReader::open(input_path)?;
+    let spec = reader.spec();
+    let sample_rate = u64::from(spec.sample_rate);
+    let file_channels = usize::from(spec.channels);
+    let samples = read_samples(&mut reader)?;
+    let frames = samples.len() / file_channels +
+	(tail * spec.sample_rate as Data) as usize;
+    
+    // ---------------------------------------------------------------
+    // Every channel of the file goes to the audio input of the same
+    // index. A mono file is fed into all inputs.
+    let ports = plugin.ports();
+    let plugin_inputs = ports.iter()
+	.filter(|port| port.kind == Some(PortKind::AudioInput))
+	.count();
+    
+    if file_channels != plugin_inputs && file_channels != 1 {
+	return Err(format!("{} has {} channels but the plugin {} audio inputs",
+			   input_path, file_channels, plugin_inputs).into());
+    }
+    
+    let inputs: Vec<Vec<Data>> = (0..plugin_inputs)
+	.map(|ch| {
+	    let ch = ch % file_channels;
+	    let mut input: Vec<Data> = samples.iter()
+		.skip(ch)
+		.step_by(file_channels)
+		.cloned()
+		.collect();
+	    input.resize(frames, 0.0);
+	    input
+	})
+	.collect();
+    
+    // ---------------------------------------------------------------
+    
+    let controls = plugin.controls(sample_rate, &changes)?;
+    let outputs = plugin.render(sample_rate, &inputs, &controls, block_size)?;
+    
+    if outputs.is_empty() {
+	return Err("the plugin does not have any audio outputs".into());
+    }
+    
+    // ---------------------------------------------------------------
+    
+    let spec = hound::WavSpec { channels: outputs.len() as u16, ..spec };
+    let mut writer = hound::WavWriter::create(output_path, spec)?;
+    
+    for frame in 0..frames {
+	for output in outputs.iter() {
+	    write_sample(&mut writer, &spec, output[frame])?;
+	}
+    }
+    writer.finalize()?;
+    
+    Ok(())
+}
+
+// -------------------------------------------------------------------
+
+// Reads all (interleaved) samples of the file scaled to [-1, 1].
+fn read_samples<R: std::io::Read>(reader: &mut hound::WavReader<R>)
+				  -> Result<Vec<Data>, hound::Error> {
+    let spec = reader.spec();
+    
+    match spec.sample_format {
+	hound::SampleFormat::Float => reader.samples::<f32>().collect(),
+	hound::SampleFormat::Int => {
+	    let scale = full_scale(spec.bits_per_sample);
+	    reader.samples::<i32>()
+		.map(|sample| sample.map(|x| x as Data / scale))
+		.collect()
+	},
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn write_sample<W>(writer: &mut hound::WavWriter<W>, spec: &hound::WavSpec,
+		   x: Data) -> Result<(), hound::Error>
+where W: std::io::Write + std::io::Seek {
+    match spec.sample_format {
+	hound::SampleFormat::Float => writer.write_sample(x),
+	hound::SampleFormat::Int => {
+	    let scale = full_scale(spec.bits_per_sample);
+	    let x = (x * scale).round().max(-scale).min(scale - 1.0);
+	    writer.write_sample(x as i32)
+	},
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn full_scale(bits_per_sample: u16) -> Data {
+    (1u64 << (bits_per_sample - 1)) as Data
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,586 @@
+// -------------------------------------------------------------------
+// Minimal LADSPA host.
+//
+// Loads a plugin library using its C `ladspa_descriptor` symbol, just
+// like any other host would, and runs its plugins over buffers held
+// in memory. It works with every LADSPA library, not just the ones
+// written in Rust.
+// -------------------------------------------------------------------
+
+use ladspa::ffi::ladspa_h;
+use ladspa::Data;
+use libloading::Library as DynamicLibrary;
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::{c_char, c_ulong};
+use std::path::Path;
+use std::sync::Mutex;
+
+// -------------------------------------------------------------------
+
+type DescriptorFunction =
+    unsafe extern "C" fn(index: c_ulong) -> *const ladspa_h::Descriptor;
+
+// Libraries may build their descriptors on the first call of
+// `ladspa_descriptor` without any locking (the ladspa crate does), so
+// the calls are serialized across all libraries.
+static DESCRIPTOR_LOCK: Mutex<()> = Mutex::new(());
+
+// -------------------------------------------------------------------
+
+#[derive(Debug)]
+pub enum Error {
+    // The shared object could not be opened or does not export
+    // `ladspa_descriptor`.
+    Load(libloading::Error),
+    UnknownPlugin(String),
+    UnknownPort(String),
+    // The number of buffers does not match the plugin's ports.
+    PortMismatch { expected: usize, found: usize },
+    InstantiationFailed,
+    // Descriptors violating the LADSPA specification, one message
+    // per problem found.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    Error::Load(err) => write!(f, "unable to load library: {}", err),
+	    Error::UnknownPlugin(label) => write!(f, "no plugin labelled '{}'", label),
+	    Error::UnknownPort(name) => write!(f, "no control input port '{}'", name),
+	    Error::PortMismatch { expected, found } =>
+		write!(f, "plugin expects {} buffers but got {}", expected, found),
+	    Error::InstantiationFailed => write!(f, "plugin could not be instantiated"),
+	    Error::Invalid(problems) => write!(f, "invalid descriptor: {}", problems.join("; ")),
+	}
+    }
+}
+
+impl std::error::Error for Error {}
+
+// -------------------------------------------------------------------
+
+// A LADSPA plugin library.
+pub struct Library {
+    descriptor_function: DescriptorFunction,
+    // Has to outlive all descriptors handed out.
+    _library: DynamicLibrary,
+}
+
+impl Library {
+
+    // ---------------------------------------------------------------
+
+    /// # Safety
+    ///
+    /// Loading the library runs its initialization code, and all
+    /// later calls trust its `ladspa_descriptor` to follow the LADSPA
+    /// specification. Only load libraries which are LADSPA plugins
+    /// and which do not misbehave when loaded.
+    pub unsafe fn load<P: AsRef<Path>>(path: P) -> Result<Library, Error> {
+	let library = DynamicLibrary::new(path.as_ref()).map_err(Error::Load)?;
+	let descriptor_function = *library
+	    .get::<DescriptorFunction>(b"ladspa_descriptor\0")
+	    .map_err(Error::Load)?;
+	
+	Ok(Library { descriptor_function, _library: library })
+    }
+
+    // ---------------------------------------------------------------
+
+    // All plugins of the library in the order of their index.
+    pub fn plugins(&self) -> Vec<Plugin<'_>> {
+	let _lock = DESCRIPTOR_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+	
+	(0..)
+	    .map(|index| unsafe { (self.descriptor_function)(index) })
+	    .take_while(|desc| !desc.is_null())
+	    .map(|desc| Plugin { desc: unsafe { &*desc } })
+	    .collect()
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn plugin(&self, label: &str) -> Result<Plugin<'_>, Error> {
+	self.plugins().into_iter()
+	    .find(|plugin| plugin.label() == label)
+	    .ok_or_else(|| Error::UnknownPlugin(label.to_string()))
+    }
+
+    // ---------------------------------------------------------------
+
+    // Checks all descriptors of the library, see `Plugin::validate()`,
+    // and that they can be told apart by unique ID and label.
+    pub fn validate(&self, sample_rate: u64) -> Result<(), Error> {
+	let plugins = self.plugins();
+	let mut problems = Vec::new();
+	
+	if plugins.is_empty() {
+	    problems.push("library exports no plugins".to_string());
+	}
+	
+	for (ii, plugin) in plugins.iter().enumerate() {
+	    let others = &plugins[..ii];
+	    if others.iter().any(|other| other.unique_id() == plugin.unique_id()) {
+		problems.push(format!("unique ID {} used more than once", plugin.unique_id()));
+	    }
+	    if others.iter().any(|other| other.label() == plugin.label()) {
+		problems.push(format!("label '{}' used more than once", plugin.label()));
+	    }
+	    if let Err(Error::Invalid(invalid)) = plugin.validate(sample_rate) {
+		problems.extend(invalid.into_iter()
+				.map(|problem| format!("{}: {}", plugin.label(), problem)));
+	    }
+	}
+	
+	if problems.is_empty() {
+	    Ok(())
+	} else {
+	    Err(Error::Invalid(problems))
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PortKind {
+    AudioInput,
+    AudioOutput,
+    ControlInput,
+    ControlOutput,
+}
+
+// -------------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct PortInfo {
+    pub index: usize,
+    pub name: String,
+    // `None` for port descriptors not valid according to the LADSPA
+    // specification.
+    pub kind: Option<PortKind>,
+    pub hint: ladspa_h::PortRangeHint,
+}
+
+impl PortInfo {
+
+    // ---------------------------------------------------------------
+
+    fn has(&self, hint: ladspa_h::PortRangeHintDescriptor) -> bool {
+	self.hint.hint_descriptor & hint != 0
+    }
+
+    // ---------------------------------------------------------------
+
+    // Bounds of the port for a particular sample rate.
+    pub fn lower_bound(&self, sample_rate: u64) -> Option<Data> {
+	if self.has(ladspa_h::HINT_BOUNDED_BELOW) {
+	    Some(self.scale(self.hint.lower_bound, sample_rate))
+	} else {
+	    None
+	}
+    }
+
+    pub fn upper_bound(&self, sample_rate: u64) -> Option<Data> {
+	if self.has(ladspa_h::HINT_BOUNDED_ABOVE) {
+	    Some(self.scale(self.hint.upper_bound, sample_rate))
+	} else {
+	    None
+	}
+    }
+
+    fn scale(&self, x: Data, sample_rate: u64) -> Data {
+	if self.has(ladspa_h::HINT_SAMPLE_RATE) {
+	    x * sample_rate as Data
+	} else {
+	    x
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn is_toggled(&self) -> bool {
+	self.has(ladspa_h::HINT_TOGGLED)
+    }
+
+    pub fn is_integer(&self) -> bool {
+	self.has(ladspa_h::HINT_INTEGER)
+    }
+
+    pub fn is_logarithmic(&self) -> bool {
+	self.has(ladspa_h::HINT_LOGARITHMIC)
+    }
+
+    // ---------------------------------------------------------------
+
+    // Default value as described by the hints or `None` if the port
+    // does not specify one. Bounds the default refers to but which
+    // are not present are taken to be 0.
+    pub fn default_value(&self, sample_rate: u64) -> Option<Data> {
+	let lower = self.lower_bound(sample_rate).unwrap_or(0.0);
+	let upper = self.upper_bound(sample_rate).unwrap_or(0.0);
+	let between = |weight: Data| {
+	    if self.is_logarithmic() && lower > 0.0 && upper > 0.0 {
+		(lower.ln() * (1.0 - weight) + upper.ln() * weight).exp()
+	    } else {
+		lower * (1.0 - weight) + upper * weight
+	    }
+	};
+	
+	let value = match self.hint.hint_descriptor & HINT_DEFAULT_MASK {
+	    ladspa_h::HINT_DEFAULT_MINIMUM => lower,
+	    ladspa_h::HINT_DEFAULT_LOW => between(0.25),
+	    ladspa_h::HINT_DEFAULT_MIDDLE => between(0.5),
+	    ladspa_h::HINT_DEFAULT_HIGH => between(0.75),
+	    ladspa_h::HINT_DEFAULT_MAXIMUM => upper,
+	    ladspa_h::HINT_DEFAULT_0 => 0.0,
+	    ladspa_h::HINT_DEFAULT_1 => 1.0,
+	    ladspa_h::HINT_DEFAULT_100 => 100.0,
+	    ladspa_h::HINT_DEFAULT_440 => 440.0,
+	    _ => return None,
+	};
+	
+	if self.is_integer() {
+	    Some(value.round())
+	} else {
+	    Some(value)
+	}
+    }
+}
+
+// Bits of the hint descriptor holding the default value.
+const HINT_DEFAULT_MASK: ladspa_h::PortRangeHintDescriptor = 0x3C0;
+
+// -------------------------------------------------------------------
+
+// Description of a plugin within a loaded library.
+#[derive(Copy, Clone)]
+pub struct Plugin<'lib> {
+    desc: &'lib ladspa_h::Descriptor,
+}
+
+impl<'lib> Plugin<'lib> {
+
+    // ---------------------------------------------------------------
+
+    pub fn unique_id(&self) -> c_ulong {
+	self.desc.unique_id
+    }
+
+    pub fn label(&self) -> String {
+	string(self.desc.label)
+    }
+
+    pub fn name(&self) -> String {
+	string(self.desc.name)
+    }
+
+    pub fn maker(&self) -> String {
+	string(self.desc.maker)
+    }
+
+    pub fn properties(&self) -> ladspa_h::Properties {
+	self.desc.properties
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn ports(&self) -> Vec<PortInfo> {
+	(0..self.desc.port_count as usize)
+	    .map(|index| unsafe {
+		let desc = *self.desc.port_descriptors.add(index);
+		
+		PortInfo {
+		    index,
+		    name: string(*self.desc.port_names.add(index)),
+		    kind: port_kind(desc),
+		    hint: *self.desc.port_range_hints.add(index),
+		}
+	    })
+	    .collect()
+    }
+
+    // ---------------------------------------------------------------
+
+    // Checks the descriptor against the LADSPA specification: names
+    // are present, port descriptors are valid and the range hints are
+    // consistent, i.e. defaults only refer to bounds which are given
+    // and lie within them.
+    pub fn validate(&self, sample_rate: u64) -> Result<(), Error> {
+	let ports = self.ports();
+	let mut problems = Vec::new();
+	
+	if self.label().is_empty() || self.label().contains(char::is_whitespace) {
+	    problems.push(format!("label '{}' is empty or contains white space",
+				  self.label()));
+	}
+	if self.name().is_empty() {
+	    problems.push("name is empty".to_string());
+	}
+	if ports.is_empty() {
+	    problems.push("plugin has no ports".to_string());
+	}
+	
+	for (ii, port) in ports.iter().enumerate() {
+	    let mut problem = |message: String| {
+		problems.push(format!("port {} '{}': {}", port.index, port.name, message));
+	    };
+	    
+	    if port.name.is_empty() {
+		problem("name is empty".to_string());
+	    }
+	    if ports[..ii].iter().any(|other| other.name == port.name) {
+		problem("name used more than once".to_string());
+	    }
+	    if port.kind.is_none() {
+		problem("not exactly one of input/output and audio/control".to_string());
+	    }
+	    
+	    let lower = port.lower_bound(sample_rate);
+	    let upper = port.upper_bound(sample_rate);
+	    
+	    if let (Some(lower), Some(upper)) = (lower, upper) {
+		if lower > upper {
+		    problem(format!("lower bound {} above upper bound {}", lower, upper));
+		}
+	    }
+	    if port.is_logarithmic() && lower.is_some_and(|lower| lower <= 0.0) {
+		problem("logarithmic range not strictly positive".to_string());
+	    }
+	    
+	    let default = port.hint.hint_descriptor & HINT_DEFAULT_MASK;
+	    let needs_lower = matches!(default, ladspa_h::HINT_DEFAULT_MINIMUM |
+				       ladspa_h::HINT_DEFAULT_LOW |
+				       ladspa_h::HINT_DEFAULT_MIDDLE |
+				       ladspa_h::HINT_DEFAULT_HIGH);
+	    let needs_upper = matches!(default, ladspa_h::HINT_DEFAULT_MAXIMUM |
+				       ladspa_h::HINT_DEFAULT_LOW |
+				       ladspa_h::HINT_DEFAULT_MIDDLE |
+				       ladspa_h::HINT_DEFAULT_HIGH);
+	    
+	    if default != 0 && port.default_value(sample_rate).is_none() {
+		problem(format!("unknown default hint {:#x}", default));
+	    }
+	    if (needs_lower && lower.is_none()) || (needs_upper && upper.is_none()) {
+		problem("default refers to a missing bound".to_string());
+	    }
+	    
+	    if let Some(value) = port.default_value(sample_rate) {
+		if lower.is_some_and(|lower| value < lower) ||
+		    upper.is_some_and(|upper| value > upper) {
+		    problem(format!("default {} out of bounds", value));
+		}
+		if port.is_toggled() && value != 0.0 && value != 1.0 {
+		    problem(format!("default {} of toggled port neither 0 nor 1", value));
+		}
+	    }
+	}
+	
+	if problems.is_empty() {
+	    Ok(())
+	} else {
+	    Err(Error::Invalid(problems))
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Control input values of the plugin, in port order, with the
+    // (name, value) pairs in `changes` applied on top of the
+    // defaults. Ports without a default are set to 0.
+    pub fn controls(&self, sample_rate: u64, changes: &[(&str, Data)])
+		    -> Result<Vec<Data>, Error> {
+	let ports: Vec<PortInfo> = self.ports().into_iter()
+	    .filter(|port| port.kind == Some(PortKind::ControlInput))
+	    .collect();
+	
+	for &(name, _) in changes {
+	    if !ports.iter().any(|port| port.name == name) {
+		return Err(Error::UnknownPort(name.to_string()));
+	    }
+	}
+	
+	Ok(ports.iter()
+	   .map(|port| changes.iter()
+		.rev()
+		.find(|&&(name, _)| name == port.name)
+		.map(|&(_, value)| value)
+		.or_else(|| port.default_value(sample_rate))
+		.unwrap_or(0.0))
+	   .collect())
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn instantiate(&self, sample_rate: u64) -> Result<Instance<'lib>, Error> {
+	let handle = (self.desc.instantiate)(self.desc, sample_rate as c_ulong);
+	
+	if handle.is_null() {
+	    Err(Error::InstantiationFailed)
+	} else {
+	    Ok(Instance { desc: self.desc, handle })
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Renders `inputs` (one buffer per audio input) through a fresh
+    // instance of the plugin in blocks of at most `block_size`
+    // samples. `controls` holds the values of the control inputs in
+    // port order, see `controls()`. Returns one buffer per audio
+    // output.
+    pub fn render(&self, sample_rate: u64, inputs: &[Vec<Data>],
+		  controls: &[Data], block_size: usize)
+		  -> Result<Vec<Vec<Data>>, Error> {
+	let ports = self.ports();
+	let count = |kind| ports.iter().filter(|port| port.kind == Some(kind)).count();
+	let length = inputs.iter().map(|input| input.len()).max().unwrap_or(0);
+	
+	if inputs.len() != count(PortKind::AudioInput) {
+	    return Err(Error::PortMismatch { expected: count(PortKind::AudioInput),
+					     found: inputs.len() });
+	}
+	if controls.len() != count(PortKind::ControlInput) {
+	    return Err(Error::PortMismatch { expected: count(PortKind::ControlInput),
+					     found: controls.len() });
+	}
+	
+	// -----------------------------------------------------------
+	// The plugin gets its own copy of all buffers as it is free to
+	// write to the control inputs.
+	let mut audio_in: Vec<Vec<Data>> = inputs.iter()
+	    .map(|input| {
+		let mut input = input.clone();
+		input.resize(length, 0.0);
+		input
+	    })
+	    .collect();
+	let mut audio_out = vec![vec![0.0; length]; count(PortKind::AudioOutput)];
+	let mut control_in = controls.to_vec();
+	let mut control_out = vec![0.0; count(PortKind::ControlOutput)];
+	let mut unused = 0.0;
+	
+	let mut instance = self.instantiate(sample_rate)?;
+	
+	unsafe {
+	    let mut next_control_in = control_in.iter_mut();
+	    let mut next_control_out = control_out.iter_mut();
+	    for port in ports.iter() {
+		let location: *mut Data = match port.kind {
+		    Some(PortKind::ControlInput) => next_control_in.next().unwrap(),
+		    Some(PortKind::ControlOutput) => next_control_out.next().unwrap(),
+		    _ => &mut unused,
+		};
+		instance.connect_port(port.index, location);
+	    }
+	}
+	instance.activate();
+	
+	for start in (0..length).step_by(block_size.max(1)) {
+	    let end = length.min(start + block_size.max(1));
+	    
+	    unsafe {
+		let mut next_audio_in = audio_in.iter_mut();
+		let mut next_audio_out = audio_out.iter_mut();
+		for port in ports.iter() {
+		    let location = match port.kind {
+			Some(PortKind::AudioInput) => next_audio_in.next(),
+			Some(PortKind::AudioOutput) => next_audio_out.next(),
+			_ => continue,
+		    };
+		    instance.connect_port(port.index,
+					  location.unwrap()[start..].as_mut_ptr());
+		}
+		instance.run(end - start);
+	    }
+	}
+	
+	instance.deactivate();
+	
+	Ok(audio_out)
+    }
+}
+
+// -------------------------------------------------------------------
+
+// A running instance of a plugin. It is cleaned up when dropped.
+pub struct Instance<'lib> {
+    desc: &'lib ladspa_h::Descriptor,
+    handle: ladspa_h::Handle,
+}
+
+impl<'lib> Instance<'lib> {
+
+    // ---------------------------------------------------------------
+
+    /// # Safety
+    ///
+    /// The plugin accesses `location` in all later calls to run()
+    /// until the port is connected elsewhere.
+    pub unsafe fn connect_port(&mut self, port: usize, location: *mut Data) {
+	(self.desc.connect_port)(self.handle, port as c_ulong, location);
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn activate(&mut self) {
+	if let Some(activate) = self.desc.activate {
+	    activate(self.handle);
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    /// # Safety
+    ///
+    /// All ports have to be connected to buffers which are still valid
+    /// and hold at least `sample_count` samples.
+    pub unsafe fn run(&mut self, sample_count: usize) {
+	(self.desc.run)(self.handle, sample_count as c_ulong);
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn deactivate(&mut self) {
+	if let Some(deactivate) = self.desc.deactivate {
+	    deactivate(self.handle);
+	}
+    }
+}
+
+impl<'lib> Drop for Instance<'lib> {
+    fn drop(&mut self) {
+	(self.desc.cleanup)(self.handle);
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn port_kind(desc: ladspa_h::PortDescriptor) -> Option<PortKind> {
+    let input = desc & ladspa_h::PORT_INPUT != 0;
+    let output = desc & ladspa_h::PORT_OUTPUT != 0;
+    let audio = desc & ladspa_h::PORT_AUDIO != 0;
+    let control = desc & ladspa_h::PORT_CONTROL != 0;
+    
+    match (input, output, audio, control) {
+	(true, false, true, false) => Some(PortKind::AudioInput),
+	(false, true, true, false) => Some(PortKind::AudioOutput),
+	(true, false, false, true) => Some(PortKind::ControlInput),
+	(false, true, false, true) => Some(PortKind::ControlOutput),
+	_ => None,
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+	String::new()
+    } else {
+	unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+    }
+}
+
+// -------------------------------------------------------------------
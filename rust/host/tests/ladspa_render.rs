@@ -0,0 +1,185 @@
+// -------------------------------------------------------------------
+// Runs the ladspa-render binary on the delay plugins of this
+// repository, the way it is used from the command line.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// -------------------------------------------------------------------
+
+const SAMPLE_RATE: u32 = 44100;
+
+const LABEL: &str = "rust_delay_5s_stereo";
+
+// The fully wet delay without interpolation.
+const WET: [&str; 5] = [
+    "Left Delay (seconds)=0.1",
+    "Right Delay (seconds)=0.2",
+    "Left Dry/Wet=1",
+    "Right Dry/Wet=1",
+    "Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)=0",
+];
+
+// -------------------------------------------------------------------
+
+// The shared object of the Rust delay. Cargo puts it next to the test
+// executables.
+fn library() -> PathBuf {
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    
+    deps.join("librust_delay_5s_stereo.so")
+}
+
+// A file of its own for every test, so they can run in parallel.
+fn temp_file(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+// -------------------------------------------------------------------
+
+fn ladspa_render(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ladspa-render"))
+	.args(args)
+	.output()
+	.unwrap()
+}
+
+// Renders `input` through the Rust delay into `output`.
+fn render(input: &Path, output: &Path, options: &[&str]) -> Output {
+    let library = library();
+    let mut args = vec!["render", library.to_str().unwrap(), LABEL,
+			input.to_str().unwrap(), output.to_str().unwrap()];
+    args.extend_from_slice(options);
+    
+    ladspa_render(&args)
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+// -------------------------------------------------------------------
+
+// Writes an impulse of the given length with one buffer per channel.
+fn write_impulse(name: &str, channels: u16, seconds: Data) -> PathBuf {
+    let path = temp_file(name);
+    let spec = hound::WavSpec {
+	channels,
+	sample_rate: SAMPLE_RATE,
+	bits_per_sample: 32,
+	sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+    
+    for ii in 0..(seconds * SAMPLE_RATE as Data) as usize {
+	for _ in 0..channels {
+	    writer.write_sample(if ii == 0 { 1.0 } else { 0.0 as Data }).unwrap();
+	}
+    }
+    writer.finalize().unwrap();
+    
+    path
+}
+
+// Reads the rendered file, one buffer per channel.
+fn read_channels(path: &Path) -> Vec<Vec<Data>> {
+    let mut reader = hound::WavReader::open(path).unwrap();
+    let channels = reader.spec().channels as usize;
+    let samples: Vec<Data> = reader.samples::<f32>().map(Result::unwrap).collect();
+    
+    (0..channels)
+	.map(|ch| samples.iter().skip(ch).step_by(channels).cloned().collect())
+	.collect()
+}
+
+// Positions of all non-zero samples.
+fn echoes(signal: &[Data]) -> Vec<usize> {
+    (0..signal.len()).filter(|&ii| signal[ii] != 0.0).collect()
+}
+
+fn samples(seconds: Data) -> usize {
+    (seconds * SAMPLE_RATE as Data).round() as usize
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn list_shows_plugins_and_ports() {
+    let output = ladspa_render(&["list", library().to_str().unwrap()]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout.lines().any(|line| line.starts_with("rust_delay_5s_stereo (400)")),
+	    "{}", stdout);
+    assert!(stdout.lines().any(|line| line.starts_with("rust_multi_tap_5s_stereo")),
+	    "{}", stdout);
+    assert!(stdout.contains("control input   Left Delay (seconds)  [0, 5] default 1"),
+	    "{}", stdout);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn render_delays_file() {
+    let input = write_impulse("stereo_in.wav", 2, 0.5);
+    let output_path = temp_file("stereo_out.wav");
+    let output = render(&input, &output_path, &WET);
+    
+    assert!(output.status.success(), "{}", stderr(&output));
+    let channels = read_channels(&output_path);
+    assert_eq!(channels.len(), 2);
+    assert_eq!(echoes(&channels[0]), vec![samples(0.1)]);
+    assert_eq!(echoes(&channels[1]), vec![samples(0.2)]);
+}
+
+// A mono file feeds all inputs, and the tail leaves room for the
+// echoes after the end of the input.
+#[test]
+fn render_feeds_mono_file_to_all_inputs() {
+    let input = write_impulse("mono_in.wav", 1, 0.05);
+    let output_path = temp_file("mono_out.wav");
+    let output = render(&input, &output_path,
+			&[&["--tail", "0.25", "--block-size", "100"], &WET[..]].concat());
+    
+    assert!(output.status.success(), "{}", stderr(&output));
+    let channels = read_channels(&output_path);
+    assert_eq!(channels.len(), 2);
+    assert_eq!(channels[0].len(), samples(0.3));
+    assert_eq!(echoes(&channels[0]), vec![samples(0.1)]);
+    assert_eq!(echoes(&channels[1]), vec![samples(0.2)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn unknown_port_is_reported() {
+    let input = write_impulse("unknown_port_in.wav", 2, 0.1);
+    let output_path = temp_file("unknown_port_out.wav");
+    let output = render(&input, &output_path, &["Delay=0.1"]);
+    
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("ladspa-render: "), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Delay"), "{}", stderr(&output));
+}
+
+#[test]
+fn unknown_plugin_is_reported() {
+    let output = ladspa_render(&["render", library().to_str().unwrap(), "no_such_plugin",
+				 "in.wav", "out.wav"]);
+    
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("no_such_plugin"), "{}", stderr(&output));
+}
+
+#[test]
+fn missing_arguments_print_usage() {
+    let output = ladspa_render(&["render", library().to_str().unwrap()]);
+    
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("Usage:"), "{}", stderr(&output));
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,244 @@
+// -------------------------------------------------------------------
+// State of a single channel of the delay.
+//
+// Every channel owns a contiguous buffer of its own. The recursive
+// part of the processing (reading the delay line, smoothing and
+// feeding back) has to be done sample by sample. It stores its
+// results in scratch space so the mixing can be done in a separate
+// pass over a whole chunk.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+use crate::filter::{Tone, ToneFilter};
+use crate::lfo::Lfo;
+use crate::mix::MixLaw;
+use crate::read_head::ReadHead;
+use crate::reverse::Reverse;
+use crate::ring_buffer::RingBuffer;
+use crate::saturation::Saturator;
+use crate::smoothing::{Glide, Smoother};
+use crate::{finite_or_zero, flush_denormal};
+
+// -------------------------------------------------------------------
+
+// Number of samples processed per chunk.
+pub const CHUNK_SIZE: usize = 64;
+
+// -------------------------------------------------------------------
+
+// Control values of the current block.
+#[derive(Copy, Clone, Default)]
+pub struct Settings {
+    // Delay in samples. In double precision, which keeps the fraction
+    // of a sample even at delays of minutes.
+    pub delay: f64,
+    // Length of the windows played backwards in reverse mode (in
+    // samples).
+    pub window: f64,
+    pub dry_wet: Data,
+    // Linear levels of the dry and wet signal for the separate mix
+    // law.
+    pub dry_level: Data,
+    pub wet_level: Data,
+    // Gain of the channel's own delayed signal and of the one of the
+    // previous channel in the feedback path.
+    pub feedback: Data,
+    pub cross_feedback: Data,
+    // Depth of the modulation in samples and phase offset of the
+    // channel's oscillator in periods.
+    pub modulation_depth: Data,
+    pub modulation_phase: Data,
+    // Whether the oscillator has any effect within the block.
+    pub modulated: bool,
+    // Number of samples repeated while the buffer is frozen.
+    pub loop_length: isize,
+}
+
+// -------------------------------------------------------------------
+
+pub struct Channel {
+    pub buf: RingBuffer<Data>,
+    pub read_head: ReadHead,
+    // Used instead of the read head in reverse mode.
+    pub reverse: Reverse,
+    pub lfo: Lfo,
+    pub filter: ToneFilter,
+    pub saturator: Saturator,
+    pub dry_wet: Smoother,
+    pub dry_level: Smoother,
+    pub wet_level: Smoother,
+    pub feedback: Smoother,
+    pub cross_feedback: Smoother,
+    pub settings: Settings,
+    // Input, delayed signal, and dry and wet gain of the current
+    // chunk.
+    pub input: [Data; CHUNK_SIZE],
+    pub delayed: [Data; CHUNK_SIZE],
+    pub dry: [Data; CHUNK_SIZE],
+    pub wet: [Data; CHUNK_SIZE],
+}
+
+impl Channel {
+
+    // ---------------------------------------------------------------
+
+    // `buffer_len` is the minimum number of samples the delay line
+    // has to hold.
+    pub fn new(buffer_len: usize) -> Channel {
+	Channel {
+	    buf: RingBuffer::new(buffer_len),
+	    read_head: ReadHead::default(),
+	    reverse: Reverse::default(),
+	    lfo: Lfo::default(),
+	    filter: ToneFilter::default(),
+	    saturator: Saturator::default(),
+	    dry_wet: Smoother::default(),
+	    dry_level: Smoother::default(),
+	    wet_level: Smoother::default(),
+	    feedback: Smoother::default(),
+	    cross_feedback: Smoother::default(),
+	    settings: Settings::default(),
+	    input: [0.0; CHUNK_SIZE],
+	    delayed: [0.0; CHUNK_SIZE],
+	    dry: [0.0; CHUNK_SIZE],
+	    wet: [0.0; CHUNK_SIZE],
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Lets the smoothed parameters start right at the current
+    // settings.
+    pub fn reset_parameters(&mut self) {
+	self.read_head.reset(self.settings.delay);
+	self.reverse.reset(self.settings.window);
+	self.lfo.reset(self.settings.modulation_depth, self.settings.modulation_phase);
+	self.dry_wet.reset(self.settings.dry_wet);
+	self.dry_level.reset(self.settings.dry_level);
+	self.wet_level.reset(self.settings.wet_level);
+	self.feedback.reset(self.settings.feedback);
+	self.cross_feedback.reset(self.settings.cross_feedback);
+    }
+
+    // ---------------------------------------------------------------
+
+    // Shortest delay (in samples) the delay line is read at until the
+    // read head has reached the current settings, taking the
+    // modulation into account. The reverse heads, if `reverse`, read
+    // right up to the most recent sample.
+    pub fn shortest_delay(&self, reverse: bool) -> f64 {
+	if reverse {
+	    return 0.0;
+	}
+	
+	let modulation = if self.settings.modulated {
+	    self.lfo.max_depth(self.settings.modulation_depth)
+	} else {
+	    0.0
+	};
+	
+	self.read_head.shortest_delay(self.settings.delay) - modulation as f64
+    }
+
+    // ---------------------------------------------------------------
+
+    // Whether the channel does nothing but delay its own input by a
+    // whole number of samples and mix it at fixed gains. The stages
+    // shared by all channels are up to the caller.
+    pub fn is_plain(&self) -> bool {
+	let settings = &self.settings;
+	
+	!settings.modulated &&
+	    self.read_head.is_settled(settings.delay) &&
+	    self.feedback.value() == 0.0 && settings.feedback == 0.0 &&
+	    self.cross_feedback.value() == 0.0 && settings.cross_feedback == 0.0 &&
+	    self.dry_wet.value() == settings.dry_wet &&
+	    self.dry_level.value() == settings.dry_level &&
+	    self.wet_level.value() == settings.wet_level
+    }
+
+    // ---------------------------------------------------------------
+
+    // Processes a whole block of a plain channel (see is_plain()),
+    // producing the same output as the sample by sample processing.
+    // The delay line is read and written a chunk at a time, and the
+    // mix is done right from the input.
+    pub fn run_plain(&mut self, input: &[Data], output: &mut [Data], mix_law: MixLaw,
+		     tone: &Tone) {
+	let settings = &self.settings;
+	let delay = settings.delay as usize;
+	let (dry, wet) = mix_law.gains(settings.dry_wet, settings.dry_level,
+				       settings.wet_level);
+	let mut start = 0;
+	
+	while start < input.len() {
+	    // Just like in the sample by sample processing a chunk is
+	    // never longer than the delay, so it is read in full before
+	    // any of it is written.
+	    let end = (start + delay.min(CHUNK_SIZE)).min(input.len());
+	    let len = end - start;
+	    
+	    self.buf.read_into(delay, &mut self.delayed[..len]);
+	    self.buf.write_from(&input[start..end], |x| flush_denormal(finite_or_zero(x)));
+	    
+	    for ((out, &input), &delayed) in output[start..end].iter_mut()
+		.zip(input[start..end].iter())
+		.zip(self.delayed[..len].iter()) {
+		    *out = input * dry + wet * delayed;
+		}
+	    
+	    // The disabled filter keeps following the signal.
+	    self.filter.process(self.delayed[len - 1], tone);
+	    start = end;
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Calculates the dry and wet gains of the next `len` samples.
+    // Once the controls have settled, which is most of the time, the
+    // gains are the same for all of them and only calculated once.
+    pub fn gains(&mut self, len: usize, mix_law: MixLaw, glide: &Glide) {
+	let settings = &self.settings;
+	
+	if self.dry_wet.value() == settings.dry_wet &&
+	    self.dry_level.value() == settings.dry_level &&
+	    self.wet_level.value() == settings.wet_level {
+		let (dry, wet) = mix_law.gains(settings.dry_wet, settings.dry_level,
+					       settings.wet_level);
+		self.dry[..len].iter_mut().for_each(|gain| *gain = dry);
+		self.wet[..len].iter_mut().for_each(|gain| *gain = wet);
+		return;
+	    }
+	
+	for ii in 0..len {
+	    let (dry, wet) = mix_law.gains(
+		self.dry_wet.next(settings.dry_wet, glide),
+		self.dry_level.next(settings.dry_level, glide),
+		self.wet_level.next(settings.wet_level, glide));
+	    self.dry[ii] = dry;
+	    self.wet[ii] = wet;
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Combines the input with the delayed signal of the current
+    // chunk, which is attenuated by the gains in `ducking`. `output`
+    // must not be longer than `CHUNK_SIZE`.
+    pub fn mix(&self, output: &mut [Data], ducking: &[Data]) {
+	let len = output.len();
+	
+	for (((((out, &input), &delayed), &dry), &wet), &ducking) in output.iter_mut()
+	    .zip(self.input[..len].iter())
+	    .zip(self.delayed[..len].iter())
+	    .zip(self.dry[..len].iter())
+	    .zip(self.wet[..len].iter())
+	    .zip(ducking[..len].iter()) {
+		*out = input * dry + wet * (ducking * delayed);
+	    }
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,105 @@
+// -------------------------------------------------------------------
+// Ducking the echoes while the input is loud.
+//
+// An envelope follower tracks the level of the input. As long as it
+// is above the threshold, the delayed signal is attenuated by as much
+// as the level exceeds it, up to the given amount. This keeps the
+// echoes from cluttering the performance and lets them bloom in its
+// gaps. Only the output is ducked, the repeats in the buffer are left
+// untouched.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+use crate::flush_denormal;
+
+// -------------------------------------------------------------------
+
+// Per block settings of the ducking.
+#[derive(Copy, Clone)]
+pub struct Ducking {
+    // Linear level above which the echoes are ducked.
+    threshold: Data,
+    // Smallest linear gain of the echoes. 1 turns the ducking off.
+    floor: Data,
+    // Coefficients of the envelope follower for rising and falling
+    // levels.
+    attack: Data,
+    release: Data,
+}
+
+impl Ducking {
+
+    // ---------------------------------------------------------------
+
+    // The threshold and amount are in dB, the attack and release
+    // times in seconds.
+    pub fn new(threshold: Data, amount: Data, attack: Data, release: Data,
+	       sample_rate: Data) -> Ducking {
+	Ducking {
+	    threshold: decibel(threshold),
+	    floor: decibel(-amount),
+	    attack: coefficient(attack * sample_rate),
+	    release: coefficient(release * sample_rate),
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Whether the echoes are ducked at all. If not, the gain is 1 no
+    // matter the level.
+    pub fn is_active(&self) -> bool {
+	self.floor < 1.0
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, Default)]
+pub struct Envelope {
+    level: Data,
+}
+
+impl Envelope {
+
+    // ---------------------------------------------------------------
+
+    pub fn reset(&mut self) {
+	self.level = 0.0;
+    }
+
+    // ---------------------------------------------------------------
+
+    // Follows the peak level of the input and returns the gain of the
+    // echoes for the current sample.
+    pub fn next(&mut self, input: Data, ducking: &Ducking) -> Data {
+	let coefficient = if input > self.level {
+	    ducking.attack
+	} else {
+	    ducking.release
+	};
+	self.level = input + flush_denormal(coefficient * (self.level - input));
+	
+	// Attenuating by the excess in dB is the same as dividing by
+	// the excess in linear terms.
+	(ducking.threshold / self.level).clamp(ducking.floor, 1.0)
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn decibel(x: Data) -> Data {
+    (10.0 as Data).powf(x / 20.0)
+}
+
+// One-pole coefficient for a time constant of `samples`. Shorter
+// times than a single sample are applied instantly.
+fn coefficient(samples: Data) -> Data {
+    if samples >= 1.0 {
+	(-1.0 / samples).exp()
+    } else {
+	0.0
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,109 @@
+// -------------------------------------------------------------------
+// Tone shaping of the repeats.
+//
+// The delayed signal passes a low-pass and a high-pass filter before
+// it is mixed and fed back. Since every repeat runs through them once
+// more, the repeats get darker and thinner over time, just like the
+// ones of analog and tape delays. Both are gentle one-pole filters
+// discretised using the bilinear transform, so the low-pass fully
+// blocks the Nyquist frequency and the high-pass passes it unaltered.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+use crate::flush_denormal;
+
+// -------------------------------------------------------------------
+
+// Per block settings shared by the filters of all channels.
+#[derive(Copy, Clone)]
+pub struct Tone {
+    // Gains of the integrators of the one-pole filters. A low-pass
+    // gain of 1 and a high-pass gain of 0 turn the respective filter
+    // off.
+    lowpass: Data,
+    highpass: Data,
+}
+
+impl Tone {
+
+    // ---------------------------------------------------------------
+
+    // Cutoff frequencies in Hz. A low-pass cutoff at or above the
+    // Nyquist frequency and a high-pass cutoff of 0 disable the
+    // respective filter.
+    pub fn new(lowpass: Data, highpass: Data, sample_rate: Data) -> Tone {
+	let gain = |cutoff: Data| {
+	    let g = (std::f32::consts::PI * cutoff / sample_rate).tan();
+	    g / (1.0 + g)
+	};
+	
+	Tone {
+	    lowpass: if lowpass >= 0.5 * sample_rate { 1.0 } else { gain(lowpass) },
+	    highpass: if highpass > 0.0 { gain(highpass) } else { 0.0 },
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Whether both filters are disabled.
+    pub fn is_neutral(&self) -> bool {
+	self.lowpass >= 1.0 && self.highpass <= 0.0
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Filter state of a single channel, the states of the integrators.
+// The high-pass is formed by subtracting a low-passed signal.
+#[derive(Copy, Clone, Default)]
+pub struct ToneFilter {
+    lowpass: Data,
+    highpass: Data,
+}
+
+impl ToneFilter {
+
+    // ---------------------------------------------------------------
+
+    pub fn reset(&mut self) {
+	self.lowpass = 0.0;
+	self.highpass = 0.0;
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn process(&mut self, x: Data, tone: &Tone) -> Data {
+	// A disabled filter has to pass the signal unaltered. The
+	// low-pass keeps following the signal though, so it can be
+	// turned on without a click.
+	let x = if tone.lowpass < 1.0 {
+	    lowpass(x, tone.lowpass, &mut self.lowpass)
+	} else {
+	    self.lowpass = x;
+	    x
+	};
+	
+	if tone.highpass > 0.0 {
+	    x - lowpass(x, tone.highpass, &mut self.highpass)
+	} else {
+	    self.highpass = 0.0;
+	    x
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// One step of a one-pole low-pass in topology-preserving form. Its
+// state decays exponentially once the input falls silent and is set to
+// 0 before it turns subnormal.
+fn lowpass(x: Data, gain: Data, state: &mut Data) -> Data {
+    let v = gain * (x - *state);
+    let y = v + *state;
+    *state = flush_denormal(y + v);
+    
+    y
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,125 @@
+// -------------------------------------------------------------------
+// Reading the delay line at fractional positions.
+//
+// The delay time is in general not a whole multiple of the sampling
+// period. Instead of truncating it, the samples around the requested
+// position are combined using one of the following strategies.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Interpolation {
+    // Truncate the delay to a whole number of samples.
+    None,
+    // Straight line between the two neighbouring samples.
+    Linear,
+    // Cubic Hermite (Catmull-Rom) spline through four samples.
+    Cubic,
+    // First order allpass filter. Flat magnitude response but it
+    // carries state from one sample to the next.
+    Allpass,
+}
+
+impl Interpolation {
+
+    // ---------------------------------------------------------------
+
+    // Maps the value of the integer control port onto a mode.
+    pub fn from_control(x: Data) -> Interpolation {
+	match x.round() as i32 {
+	    1 => Interpolation::Linear,
+	    2 => Interpolation::Cubic,
+	    3 => Interpolation::Allpass,
+	    _ => Interpolation::None,
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Shortest delay (in samples) for which all samples required by
+    // `read()` have already been written to the buffer.
+    pub fn min_delay(self) -> f64 {
+	match self {
+	    Interpolation::None | Interpolation::Linear => 1.0,
+	    Interpolation::Cubic => 2.0,
+	    Interpolation::Allpass => 1.5,
+	}
+    }
+    
+    // Splits a delay given in samples into the integer part used to
+    // address the buffer and the remaining fraction. The allpass is
+    // only well behaved for fractions between 0.5 and 1.5 which is
+    // why its integer part is shifted by one sample.
+    pub fn split(self, delay: f64) -> (usize, Data) {
+	// Delays are never negative, so truncating rounds down just like
+	// floor() would, which is a library call on plain x86-64.
+	let whole = delay as i64;
+	let frac = (delay - whole as f64) as Data;
+	let whole = whole as usize;
+	
+	match self {
+	    Interpolation::None => (whole, 0.0),
+	    Interpolation::Allpass if frac < 0.5 && whole > 0 =>
+		(whole - 1, frac + 1.0),
+	    _ => (whole, frac),
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Reads the delayed signal. `sample(k)` has to return the sample
+    // `k` steps further in the past than the integer read position
+    // (with `k == -1` being one step closer to the present) and
+    // `frac` is the fractional part as returned by `split()`.
+    pub fn read<F>(self, sample: F, frac: Data, allpass: &mut Allpass) -> Data
+    where F: Fn(isize) -> Data {
+	match self {
+	    Interpolation::None => sample(0),
+	    Interpolation::Linear => linear(sample(0), sample(1), frac),
+	    Interpolation::Cubic => hermite(sample(-1), sample(0),
+					    sample(1), sample(2), frac),
+	    Interpolation::Allpass => allpass.process(sample(0), sample(1),
+						      frac),
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn linear(x0: Data, x1: Data, frac: Data) -> Data {
+    x0 + frac * (x1 - x0)
+}
+
+// -------------------------------------------------------------------
+
+fn hermite(xm1: Data, x0: Data, x1: Data, x2: Data, frac: Data) -> Data {
+    let c1 = 0.5 * (x1 - xm1);
+    let c2 = xm1 - 2.5 * x0 + 2.0 * x1 - 0.5 * x2;
+    let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+    
+    ((c3 * frac + c2) * frac + c1) * frac + x0
+}
+
+// -------------------------------------------------------------------
+
+// State of the allpass interpolator of a single channel.
+#[derive(Copy, Clone, Default)]
+pub struct Allpass {
+    last_output: Data,
+}
+
+impl Allpass {
+
+    // ---------------------------------------------------------------
+
+    fn process(&mut self, x0: Data, x1: Data, frac: Data) -> Data {
+	let eta = (1.0 - frac) / (1.0 + frac);
+	self.last_output = eta * (x0 - self.last_output) + x1;
+	self.last_output
+    }
+}
+
+// -------------------------------------------------------------------
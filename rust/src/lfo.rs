@@ -0,0 +1,165 @@
+// -------------------------------------------------------------------
+// Low frequency oscillator modulating the read head.
+//
+// Slowly moving the read position bends the pitch of the repeats up
+// and down, resulting in the wow and flutter of a tape delay or, with
+// larger depths, chorused repeats. Every channel has an oscillator of
+// its own. All of them run at the same rate but each one may be
+// shifted in phase to widen the stereo image.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+use crate::smoothing::{Glide, Smoother};
+
+// -------------------------------------------------------------------
+
+// Start of the pseudo-random sequence. Fixed so renderings are
+// reproducible.
+const SEED: u32 = 0x9E37_79B9;
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    // A new random value each period, approached along a cosine
+    // segment.
+    Random,
+}
+
+impl Waveform {
+
+    // ---------------------------------------------------------------
+
+    // Maps the value of the integer control port onto a waveform.
+    pub fn from_control(x: Data) -> Waveform {
+	match x.round() as i32 {
+	    1 => Waveform::Triangle,
+	    2 => Waveform::Random,
+	    _ => Waveform::Sine,
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, Default)]
+pub struct Lfo {
+    // Position within the period, without the phase offset. Kept in
+    // double precision since single precision would make the
+    // oscillator drift noticeably within a few periods.
+    phase: f64,
+    // Phase offset (in periods) and depth (in samples).
+    offset: Smoother,
+    depth: Smoother,
+    // Position within the period including the offset at the last
+    // sample. Used to detect the start of a new period.
+    last_phase: Data,
+    // The random waveform moves from `previous` to `next` within a
+    // period.
+    previous: Data,
+    next: Data,
+    random: u32,
+}
+
+impl Lfo {
+
+    // ---------------------------------------------------------------
+
+    pub fn reset(&mut self, depth: Data, offset: Data) {
+	self.phase = 0.0;
+	self.offset.reset(offset);
+	self.depth.reset(depth);
+	self.last_phase = wrap(offset);
+	self.random = SEED;
+	self.previous = 0.0;
+	self.next = self.random();
+    }
+
+    // ---------------------------------------------------------------
+
+    // Offset of the read head (in samples) for the current sample.
+    // `step` is the rate of the oscillator relative to the sample
+    // rate.
+    pub fn next(&mut self, waveform: Waveform, step: f64, depth: Data,
+		offset: Data, glide: &Glide) -> Data {
+	let depth = self.depth.next(depth, glide);
+	let phase = wrap(self.phase as Data + self.offset.next(offset, glide));
+	
+	if phase < self.last_phase {
+	    self.previous = self.next;
+	    self.next = self.random();
+	}
+	self.last_phase = phase;
+	self.phase += step;
+	self.phase -= self.phase.floor();
+
+	// -----------------------------------------------------------
+	// Without modulation the read head has to stay exactly where
+	// it is.
+	if depth == 0.0 {
+	    return 0.0;
+	}
+	
+	let value = match waveform {
+	    Waveform::Sine => (2.0 * std::f32::consts::PI * phase).sin(),
+	    Waveform::Triangle => 4.0 * (wrap(phase + 0.75) - 0.5).abs() - 1.0,
+	    Waveform::Random => {
+		let weight = 0.5 - 0.5 * (std::f32::consts::PI * phase).cos();
+		self.previous + weight * (self.next - self.previous)
+	    },
+	};
+	
+	depth * value
+    }
+
+    // ---------------------------------------------------------------
+
+    // Whether the oscillator has no effect at the given depth, which
+    // is the case once its depth has settled at 0.
+    pub fn is_idle(&self, depth: Data) -> bool {
+	depth == 0.0 && self.depth.value() == 0.0
+    }
+
+    // ---------------------------------------------------------------
+
+    // Largest depth reached on the way to `depth`.
+    pub fn max_depth(&self, depth: Data) -> Data {
+	self.depth.value().max(depth)
+    }
+
+    // ---------------------------------------------------------------
+
+    // Moves the oscillator on by `samples` without computing any
+    // output, keeping it in step with the oscillators that are still
+    // running. The phase offset is applied right away since it cannot
+    // be heard either.
+    pub fn skip(&mut self, step: f64, offset: Data, samples: usize) {
+	self.phase += step * samples as f64;
+	self.phase -= self.phase.floor();
+	self.offset.reset(offset);
+	self.last_phase = wrap(self.phase as Data + offset);
+    }
+
+    // ---------------------------------------------------------------
+
+    // Next value of a xorshift generator, scaled to [-1, 1].
+    fn random(&mut self) -> Data {
+	self.random ^= self.random << 13;
+	self.random ^= self.random >> 17;
+	self.random ^= self.random << 5;
+	
+	self.random as Data / u32::MAX as Data * 2.0 - 1.0
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Position within a period, i.e. the fractional part.
+fn wrap(phase: Data) -> Data {
+    phase - phase.floor()
+}
+
+// -------------------------------------------------------------------
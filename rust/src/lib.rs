@@ -1,29 +1,550 @@
 extern crate ladspa;
 
-use ladspa::{PluginDescriptor, PortDescriptor, Port, DefaultValue,
+use ladspa::{PluginDescriptor, PortDescriptor, Port, DefaultValue, ControlHint,
 	     Data, Plugin, PortConnection};
 use std::default::Default;
 
+mod channel;
+mod ducking;
+mod filter;
+mod interpolation;
+mod lfo;
+mod mix;
+mod multi_tap;
+mod read_head;
+mod reverse;
+mod ring_buffer;
+mod saturation;
+mod smoothing;
+mod stereo;
+mod tempo;
+
+use channel::{Channel, CHUNK_SIZE};
+use ducking::{Ducking, Envelope};
+use filter::Tone;
+use interpolation::Interpolation;
+use lfo::Waveform;
+use mix::MixLaw;
+use reverse::Direction;
+use saturation::{Curve, Drive, Halfband};
+use smoothing::Glide;
+use stereo::StereoMode;
+
+// -------------------------------------------------------------------
+
+// Number of samples the buffer has to be larger than the maximum
+// delay. They are required by the interpolation.
+const BUFFER_MARGIN: usize = 3;
+
+// Upper limit of the feedback gain. It is kept strictly below 1 so
+// the repeats are guaranteed to decay no matter what the host sends.
+const MAX_FEEDBACK: Data = 0.99;
+
+// Upper limit of the time constant used to smooth parameter changes
+// (in seconds).
+const MAX_SMOOTHING: Data = 0.2;
+
+// Range of the modulation rate (in Hz) and maximum modulation depth
+// (in seconds). The buffers are enlarged by the depth, so the read
+// head can be modulated even at the maximum delay.
+const MIN_MODULATION_RATE: Data = 0.05;
+const MAX_MODULATION_RATE: Data = 20.0;
+const MAX_MODULATION_DEPTH: Data = 0.02;
+
+// Range of the cutoff frequencies of the filters in the feedback path,
+// relative to the sample rate. The low-pass is off at its maximum, the
+// Nyquist frequency, and the high-pass at its minimum.
+const MIN_LOWPASS: Data = 0.001;
+const MAX_HIGHPASS: Data = 0.1;
+
+// Maximum gain (in dB) in front of the saturation curve.
+const MAX_DRIVE: Data = 24.0;
+
+// Range of the tempo (in beats per minute) delay times can be synced
+// to. Its default, the middle, is 120 BPM.
+const MIN_TEMPO: Data = 20.0;
+const MAX_TEMPO: Data = 220.0;
+
+// Length of the cross-fade (in seconds) between the incoming signal
+// and the loop when freezing or releasing the buffer. It also hides
+// the seam at the loop boundary.
+const FREEZE_FADE: Data = 0.01;
+
+// Length of the cross-fade (in seconds) between the forward and the
+// reverse read heads when the direction is switched.
+const DIRECTION_FADE: Data = 0.01;
+
+// Range of the level (in dB) above which the echoes are ducked, the
+// maximum attenuation (in dB), and the maximum attack and release
+// times (in seconds) of the envelope follower.
+const MIN_DUCKING_THRESHOLD: Data = -60.0;
+const MAX_DUCKING: Data = 40.0;
+const MAX_ATTACK: Data = 0.1;
+const MAX_RELEASE: Data = 2.0;
+
+// Maximum stereo width of the delayed signal. 1 leaves it unchanged.
+const MAX_WIDTH: Data = 2.0;
+
+// Level below which the recirculating signals are set to 0 (about
+// -400 dB).
+const DENORMAL_THRESHOLD: Data = 1e-20;
+
 // -------------------------------------------------------------------
 
-const MAX_DELAY: Data = 5.0;
+// The delays are stereo. The names of the ports, the mid/side coding
+// and the width all assume a left and a right channel.
+const CHANNELS: usize = 2;
+
+// Layout of the ports. The two audio inputs come first, followed by the
+// two audio outputs. Afterwards the controls follow in the order given
+// below, each one described by its entry in CONTROLS. New controls are
+// appended so existing ports keep their indices.
+const DELAY_CONTROL: usize = 0;
+const DRY_WET_CONTROL: usize = 1;
+const FEEDBACK_CONTROL: usize = 2;
+const INTERPOLATION_CONTROL: usize = 3;
+const SMOOTHING_CONTROL: usize = 4;
+// Feedback from a channel into the next one.
+const CROSS_FEEDBACK_CONTROL: usize = 5;
+const PING_PONG_CONTROL: usize = 6;
+// With tempo sync enabled, the note value and modifier replace the
+// delay time in seconds.
+const TEMPO_SYNC_CONTROL: usize = 7;
+const TEMPO_CONTROL: usize = 8;
+const NOTE_VALUE_CONTROL: usize = 9;
+const NOTE_MODIFIER_CONTROL: usize = 10;
+const MODULATION_WAVEFORM_CONTROL: usize = 11;
+const MODULATION_RATE_CONTROL: usize = 12;
+const MODULATION_DEPTH_CONTROL: usize = 13;
+// Phase offset between the oscillators of neighbouring channels.
+const MODULATION_PHASE_CONTROL: usize = 14;
+const LOWPASS_CONTROL: usize = 15;
+const HIGHPASS_CONTROL: usize = 16;
+const SATURATION_CONTROL: usize = 17;
+const DRIVE_CONTROL: usize = 18;
+// While frozen, the buffers loop their content instead of recording
+// the input. A loop length of 0 loops the delay time.
+const FREEZE_CONTROL: usize = 19;
+const LOOP_LENGTH_CONTROL: usize = 20;
+const DIRECTION_CONTROL: usize = 21;
+const DUCKING_THRESHOLD_CONTROL: usize = 22;
+const DUCKING_CONTROL: usize = 23;
+const ATTACK_CONTROL: usize = 24;
+const RELEASE_CONTROL: usize = 25;
+// In mid/side mode the first channel carries the mid and the second
+// one the side signal, so the per-channel controls of the left
+// channel apply to mid and those of the right one to side.
+const STEREO_MODE_CONTROL: usize = 26;
+const WIDTH_CONTROL: usize = 27;
+// The dry and wet levels only apply to the separate mix law, which
+// ignores the dry/wet control.
+const MIX_LAW_CONTROL: usize = 28;
+const DRY_LEVEL_CONTROL: usize = 29;
+const WET_LEVEL_CONTROL: usize = 30;
+
+// -------------------------------------------------------------------
+
+// Description of a control. Per-channel controls occupy one port for
+// each channel, named after the channel, the others a single port
+// shared by all channels.
+struct Control {
+    names: Names,
+    hints: &'static [ControlHint],
+    default: Option<DefaultValue>,
+    lower_bound: Option<Data>,
+    upper_bound: Option<Bound>,
+}
+
+enum Names {
+    Shared(&'static str),
+    PerChannel([&'static str; CHANNELS]),
+}
+
+// Some upper bounds depend on the variant of the delay.
+#[derive(Copy, Clone)]
+enum Bound {
+    Value(Data),
+    MaxDelay,
+}
+
+impl Control {
+
+    // ---------------------------------------------------------------
+
+    fn port_count(&self) -> usize {
+	match self.names {
+	    Names::Shared(_) => 1,
+	    Names::PerChannel(_) => CHANNELS,
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    fn ports(&self, max_delay: Data) -> Vec<Port> {
+	let names = match self.names {
+	    Names::Shared(ref name) => std::slice::from_ref(name),
+	    Names::PerChannel(ref names) => &names[..],
+	};
+	// The flags of a hint cannot be combined in a constant.
+	let hint = self.hints.iter().fold(None, |hint: Option<ControlHint>, &flag| {
+	    Some(hint.map_or(flag, |hint| hint | flag))
+	});
+	let upper_bound = self.upper_bound.map(|bound| match bound {
+	    Bound::Value(value) => value,
+	    Bound::MaxDelay => max_delay,
+	});
+	
+	names.iter().map(|&name| Port {
+	    name,
+	    desc: PortDescriptor::ControlInput,
+	    hint,
+	    default: self.default,
+	    lower_bound: self.lower_bound,
+	    upper_bound,
+	}).collect()
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The controls in the order of the indices above.
+const CONTROLS: [Control; 31] = [
+    Control {
+	names: Names::PerChannel(["Left Delay (seconds)", "Right Delay (seconds)"]),
+	hints: &[],
+	default: Some(DefaultValue::Value1),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::MaxDelay),
+    },
+    Control {
+	names: Names::PerChannel(["Left Dry/Wet", "Right Dry/Wet"]),
+	hints: &[],
+	default: Some(DefaultValue::Middle),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(1.0)),
+    },
+    Control {
+	names: Names::PerChannel(["Left Feedback", "Right Feedback"]),
+	hints: &[],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_FEEDBACK)),
+    },
+    Control {
+	names: Names::Shared("Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)"),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Value1),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(3.0)),
+    },
+    Control {
+	names: Names::Shared("Smoothing Time (seconds)"),
+	hints: &[],
+	default: Some(DefaultValue::Low),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_SMOOTHING)),
+    },
+    Control {
+	names: Names::PerChannel(["Left to Right Feedback", "Right to Left Feedback"]),
+	hints: &[],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_FEEDBACK)),
+    },
+    Control {
+	names: Names::Shared("Ping-Pong"),
+	hints: &[ladspa::HINT_TOGGLED],
+	default: Some(DefaultValue::Value0),
+	lower_bound: None,
+	upper_bound: None,
+    },
+    Control {
+	names: Names::Shared("Tempo Sync"),
+	hints: &[ladspa::HINT_TOGGLED],
+	default: Some(DefaultValue::Value0),
+	lower_bound: None,
+	upper_bound: None,
+    },
+    Control {
+	names: Names::Shared("Tempo (BPM)"),
+	hints: &[],
+	default: Some(DefaultValue::Middle),
+	lower_bound: Some(MIN_TEMPO),
+	upper_bound: Some(Bound::Value(MAX_TEMPO)),
+    },
+    Control {
+	names: Names::PerChannel(["Left Note Value (0: 1/1, 1: 1/2, 2: 1/4, 3: 1/8, 4: 1/16, 5: 1/32, 6: 1/64)", "Right Note Value (0: 1/1, 1: 1/2, 2: 1/4, 3: 1/8, 4: 1/16, 5: 1/32, 6: 1/64)"]),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Middle),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(tempo::SHORTEST_NOTE_VALUE as Data)),
+    },
+    Control {
+	names: Names::PerChannel(["Left Note Modifier (0: straight, 1: dotted, 2: triplet)", "Right Note Modifier (0: straight, 1: dotted, 2: triplet)"]),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(2.0)),
+    },
+    Control {
+	names: Names::Shared("Modulation Waveform (0: sine, 1: triangle, 2: random)"),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(2.0)),
+    },
+    Control {
+	names: Names::Shared("Modulation Rate (Hz)"),
+	hints: &[ladspa::HINT_LOGARITHMIC],
+	default: Some(DefaultValue::Middle),
+	lower_bound: Some(MIN_MODULATION_RATE),
+	upper_bound: Some(Bound::Value(MAX_MODULATION_RATE)),
+    },
+    Control {
+	names: Names::Shared("Modulation Depth (seconds)"),
+	hints: &[],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_MODULATION_DEPTH)),
+    },
+    Control {
+	names: Names::Shared("Modulation Stereo Phase (degrees)"),
+	hints: &[],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(360.0)),
+    },
+    Control {
+	names: Names::Shared("Low-Pass Cutoff (Hz)"),
+	hints: &[ladspa::HINT_SAMPLE_RATE, ladspa::HINT_LOGARITHMIC],
+	default: Some(DefaultValue::Maximum),
+	lower_bound: Some(MIN_LOWPASS),
+	upper_bound: Some(Bound::Value(0.5)),
+    },
+    Control {
+	names: Names::Shared("High-Pass Cutoff (Hz)"),
+	hints: &[ladspa::HINT_SAMPLE_RATE],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_HIGHPASS)),
+    },
+    Control {
+	names: Names::Shared("Saturation (0: off, 1: tanh, 2: tube, 3: tape)"),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(3.0)),
+    },
+    Control {
+	names: Names::Shared("Saturation Drive (dB)"),
+	hints: &[],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_DRIVE)),
+    },
+    Control {
+	names: Names::Shared("Freeze"),
+	hints: &[ladspa::HINT_TOGGLED],
+	default: Some(DefaultValue::Value0),
+	lower_bound: None,
+	upper_bound: None,
+    },
+    Control {
+	names: Names::Shared("Freeze Loop Length (seconds, 0: delay time)"),
+	hints: &[],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::MaxDelay),
+    },
+    Control {
+	names: Names::Shared("Direction (0: forward, 1: reverse up to half the max delay)"),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(1.0)),
+    },
+    Control {
+	names: Names::Shared("Ducking Threshold (dB)"),
+	hints: &[],
+	default: Some(DefaultValue::Middle),
+	lower_bound: Some(MIN_DUCKING_THRESHOLD),
+	upper_bound: Some(Bound::Value(0.0)),
+    },
+    Control {
+	names: Names::Shared("Ducking Amount (dB)"),
+	hints: &[],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_DUCKING)),
+    },
+    Control {
+	names: Names::Shared("Ducking Attack (seconds)"),
+	hints: &[],
+	default: Some(DefaultValue::Low),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_ATTACK)),
+    },
+    Control {
+	names: Names::Shared("Ducking Release (seconds)"),
+	hints: &[],
+	default: Some(DefaultValue::Low),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_RELEASE)),
+    },
+    Control {
+	names: Names::Shared("Stereo Mode (0: left/right, 1: mid/side)"),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(1.0)),
+    },
+    Control {
+	names: Names::Shared("Stereo Width"),
+	hints: &[],
+	default: Some(DefaultValue::Value1),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(MAX_WIDTH)),
+    },
+    Control {
+	names: Names::Shared("Mix Law (0: linear, 1: equal-power, 2: separate levels)"),
+	hints: &[ladspa::HINT_INTEGER],
+	default: Some(DefaultValue::Minimum),
+	lower_bound: Some(0.0),
+	upper_bound: Some(Bound::Value(2.0)),
+    },
+    Control {
+	names: Names::Shared("Dry Level (dB)"),
+	hints: &[],
+	default: Some(DefaultValue::Value0),
+	lower_bound: Some(mix::MIN_LEVEL),
+	upper_bound: Some(Bound::Value(mix::MAX_LEVEL)),
+    },
+    Control {
+	names: Names::Shared("Wet Level (dB)"),
+	hints: &[],
+	default: Some(DefaultValue::Value0),
+	lower_bound: Some(mix::MIN_LEVEL),
+	upper_bound: Some(Bound::Value(mix::MAX_LEVEL)),
+    },
+];
+
+// -------------------------------------------------------------------
+
+// The members of the plugin family. They only differ in the maximum
+// delay (in seconds) and hence the memory they allocate.
+struct Variant {
+    unique_id: u64,
+    label: &'static str,
+    name: &'static str,
+    max_delay: Data,
+}
+
+const VARIANTS: [Variant; 4] = [
+    Variant {
+	unique_id: 400,
+	label: "rust_delay_5s_stereo",
+	name: "LADSPA Stereo Delay example in Rust (5 s)",
+	max_delay: 5.0,
+    },
+    Variant {
+	unique_id: 401,
+	label: "rust_delay_1s_stereo",
+	name: "LADSPA Stereo Delay example in Rust (1 s)",
+	max_delay: 1.0,
+    },
+    Variant {
+	unique_id: 402,
+	label: "rust_delay_30s_stereo",
+	name: "LADSPA Stereo Delay example in Rust (30 s)",
+	max_delay: 30.0,
+    },
+    Variant {
+	unique_id: 403,
+	label: "rust_delay_120s_stereo",
+	name: "LADSPA Stereo Delay example in Rust (120 s)",
+	max_delay: 120.0,
+    },
+];
 
 // -------------------------------------------------------------------
 
 struct Delay {
     sample_rate: Data,
-    buf: Vec<(Data, Data)>,
-    buf_idx: usize,
+    max_delay: Data,
+    channels: [Channel; CHANNELS],
+    halfband: Halfband,
+    // Weight of the loop in the signal written to the buffers. Moves
+    // between 0 (recording) and 1 (frozen) during the cross-fades.
+    freeze: Data,
+    // Weight of the reverse read heads in the delayed signal. Moves
+    // between 0 (forward) and 1 (reverse) during the cross-fades.
+    reversed: Data,
+    // Level of the input driving the ducking, shared by all channels,
+    // and the resulting gain of the echoes in the current chunk.
+    envelope: Envelope,
+    ducking_gain: [Data; CHUNK_SIZE],
+    // Set in activate() to let the smoothed parameters start right at
+    // the values of the first run().
+    fresh: bool,
 }
 
 // -------------------------------------------------------------------
 
-fn new_delay(_: &PluginDescriptor, sample_rate: u64) -> Box<Plugin + Send> {
-    Box::new(Delay {
-	sample_rate: sample_rate as Data,
-	buf: Vec::new(),
-	buf_idx: 0,
-    })
+fn new_delay(desc: &PluginDescriptor, sample_rate: u64) -> Box<dyn Plugin + Send> {
+    let variant = VARIANTS.iter()
+	.find(|variant| variant.unique_id == desc.unique_id)
+	.expect("descriptor of an unknown variant");
+    
+    Box::new(Delay::new(sample_rate as Data, variant.max_delay))
+}
+
+// -------------------------------------------------------------------
+
+impl Delay {
+
+    // ---------------------------------------------------------------
+
+    // All memory required by the delay is allocated right here, so
+    // neither activate() nor run() have to.
+    fn new(sample_rate: Data, max_delay: Data) -> Delay {
+	let buffer_len = (sample_rate * (max_delay + MAX_MODULATION_DEPTH)) as usize +
+	    BUFFER_MARGIN;
+	
+	Delay {
+	    sample_rate,
+	    max_delay,
+	    channels: [Channel::new(buffer_len), Channel::new(buffer_len)],
+	    halfband: Halfband::new(),
+	    freeze: 0.0,
+	    reversed: 0.0,
+	    envelope: Envelope::default(),
+	    ducking_gain: [1.0; CHUNK_SIZE],
+	    fresh: true,
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Index of the first port of a control.
+    fn control_port(&self, control: usize) -> usize {
+	2 * CHANNELS + CONTROLS[..control].iter()
+	    .map(Control::port_count)
+	    .sum::<usize>()
+    }
+
+    // ---------------------------------------------------------------
+
+    fn channel_control<'a>(&self, ports: &[&'a PortConnection<'a>],
+			   control: usize, channel: usize) -> Data {
+	*ports[self.control_port(control) + channel].unwrap_control()
+    }
+
+    // ---------------------------------------------------------------
+
+    fn shared_control<'a>(&self, ports: &[&'a PortConnection<'a>],
+			  control: usize) -> Data {
+	*ports[self.control_port(control)].unwrap_control()
+    }
 }
 
 // -------------------------------------------------------------------
@@ -33,62 +554,382 @@ impl Plugin for Delay {
     // ---------------------------------------------------------------
 
     fn activate(&mut self) {
-	self.buf.clear();
-	self.buf.resize((self.sample_rate * MAX_DELAY * 1.0) as usize + 1,
-			(0.0, 0.0));
-	self.buf_idx = 0;
+	for channel in self.channels.iter_mut() {
+	    channel.buf.clear();
+	    channel.filter.reset();
+	    channel.saturator.reset();
+	}
+	self.freeze = 0.0;
+	self.envelope.reset();
+	self.fresh = true;
     }
     
     // ---------------------------------------------------------------
     
     fn run<'a>(&mut self, sample_count: usize, ports: &[&'a PortConnection<'a>]) {
-	let input = (ports[0].unwrap_audio(), ports[1].unwrap_audio());
-	let mut output = (ports[2].unwrap_audio_mut(), ports[3].unwrap_audio_mut());
+	// -----------------------------------------------------------
+	// All control values are limited to the range advertised in the
+	// descriptor since the host is not obliged to respect it.
+	
+	let interpolation = Interpolation::from_control(
+	    self.shared_control(ports, INTERPOLATION_CONTROL));
+	let glide = Glide::new(
+	    limit(self.shared_control(ports, SMOOTHING_CONTROL), 0.0,
+		  MAX_SMOOTHING),
+	    self.sample_rate);
+	let ping_pong = toggled(self.shared_control(ports, PING_PONG_CONTROL));
+	let tempo_sync = toggled(self.shared_control(ports, TEMPO_SYNC_CONTROL));
+	let tempo = limit(self.shared_control(ports, TEMPO_CONTROL), MIN_TEMPO, MAX_TEMPO);
+	let waveform = Waveform::from_control(
+	    self.shared_control(ports, MODULATION_WAVEFORM_CONTROL));
+	let modulation_step = limit(self.shared_control(ports, MODULATION_RATE_CONTROL),
+				    MIN_MODULATION_RATE, MAX_MODULATION_RATE) as f64 /
+	    self.sample_rate as f64;
+	let modulation_depth = limit(self.shared_control(ports, MODULATION_DEPTH_CONTROL),
+				     0.0, MAX_MODULATION_DEPTH) * self.sample_rate;
+	let modulation_phase = limit(self.shared_control(ports, MODULATION_PHASE_CONTROL),
+				     0.0, 360.0) / 360.0;
+	let tone = Tone::new(
+	    limit(self.shared_control(ports, LOWPASS_CONTROL),
+		  MIN_LOWPASS * self.sample_rate, 0.5 * self.sample_rate),
+	    limit(self.shared_control(ports, HIGHPASS_CONTROL),
+		  0.0, MAX_HIGHPASS * self.sample_rate),
+	    self.sample_rate);
+	let drive = Drive::new(
+	    Curve::from_control(self.shared_control(ports, SATURATION_CONTROL)),
+	    (10.0 as Data).powf(
+		limit(self.shared_control(ports, DRIVE_CONTROL), 0.0, MAX_DRIVE) / 20.0),
+	    self.sample_rate);
+	let ducking = Ducking::new(
+	    limit(self.shared_control(ports, DUCKING_THRESHOLD_CONTROL),
+		  MIN_DUCKING_THRESHOLD, 0.0),
+	    limit(self.shared_control(ports, DUCKING_CONTROL), 0.0, MAX_DUCKING),
+	    limit(self.shared_control(ports, ATTACK_CONTROL), 0.0, MAX_ATTACK),
+	    limit(self.shared_control(ports, RELEASE_CONTROL), 0.0, MAX_RELEASE),
+	    self.sample_rate);
+	let mix_law = MixLaw::from_control(self.shared_control(ports, MIX_LAW_CONTROL));
+	let dry_level = mix::level(limit(self.shared_control(ports, DRY_LEVEL_CONTROL),
+					 mix::MIN_LEVEL, mix::MAX_LEVEL));
+	let wet_level = mix::level(limit(self.shared_control(ports, WET_LEVEL_CONTROL),
+					 mix::MIN_LEVEL, mix::MAX_LEVEL));
+	let stereo_mode = StereoMode::from_control(
+	    self.shared_control(ports, STEREO_MODE_CONTROL));
+	let width = limit(self.shared_control(ports, WIDTH_CONTROL), 0.0, MAX_WIDTH);
+	let direction = Direction::from_control(
+	    self.shared_control(ports, DIRECTION_CONTROL));
+	let direction_step = 1.0 / (DIRECTION_FADE * self.sample_rate);
+	// Played backwards, a window reaches twice its length into the
+	// past.
+	let max_window = 0.5 * self.max_delay as f64 * self.sample_rate as f64;
+	let freeze = toggled(self.shared_control(ports, FREEZE_CONTROL));
+	let freeze_step = 1.0 / (FREEZE_FADE * self.sample_rate);
+	let loop_length = limit(self.shared_control(ports, LOOP_LENGTH_CONTROL),
+				0.0, self.max_delay) * self.sample_rate;
 	
 	// -----------------------------------------------------------
 
-	let delay = ((*ports[4].unwrap_control() * self.sample_rate) as usize,
-		     (*ports[5].unwrap_control() * self.sample_rate) as usize);
+	let min_delay = interpolation.min_delay();
+	// The saturation stage delays the signal a little, which is
+	// made up for by reading the delay line a bit later.
+	let latency = if drive.curve == Curve::Off {
+	    0.0
+	} else {
+	    saturation::LATENCY as f64
+	};
+	
+	for ch in 0..CHANNELS {
+	    let previous = (ch + CHANNELS - 1) % CHANNELS;
+	    let delay = if tempo_sync {
+		tempo::note_length(
+		    tempo,
+		    self.channel_control(ports, NOTE_VALUE_CONTROL, ch),
+		    self.channel_control(ports, NOTE_MODIFIER_CONTROL, ch))
+	    } else {
+		self.channel_control(ports, DELAY_CONTROL, ch)
+	    };
+	    let dry_wet = self.channel_control(ports, DRY_WET_CONTROL, ch);
+	    
+	    // In ping-pong mode the echoes bounce from one channel to the
+	    // next, each channel's feedback control determining how much
+	    // of its echo is passed on.
+	    let (feedback, cross_feedback) = if ping_pong {
+		(0.0, self.channel_control(ports, FEEDBACK_CONTROL, previous))
+	    } else {
+		(self.channel_control(ports, FEEDBACK_CONTROL, ch),
+		 self.channel_control(ports, CROSS_FEEDBACK_CONTROL, previous))
+	    };
+	    let feedback = limit(feedback, 0.0, MAX_FEEDBACK);
+	    let cross_feedback = limit(cross_feedback, 0.0, MAX_FEEDBACK);
+	    
+	    // The total gain fed back into a channel must not exceed
+	    // MAX_FEEDBACK either, otherwise the repeats would build up.
+	    let total = feedback + cross_feedback;
+	    let scale = if total > MAX_FEEDBACK { MAX_FEEDBACK / total } else { 1.0 };
+	    
+	    let settings = &mut self.channels[ch].settings;
+	    
+	    // Delay in samples.
+	    settings.delay = (limit(delay, 0.0, self.max_delay) as f64 *
+			      self.sample_rate as f64 - latency).max(min_delay);
+	    settings.dry_wet = limit(dry_wet, 0.0, 1.0);
+	    settings.dry_level = dry_level;
+	    settings.wet_level = wet_level;
+	    settings.feedback = feedback * scale;
+	    settings.cross_feedback = cross_feedback * scale;
+	    settings.modulation_depth = modulation_depth;
+	    settings.modulation_phase = modulation_phase * ch as Data;
+	    // Whole samples, so the loop is copied without interpolation.
+	    settings.loop_length = if loop_length > 0.0 {
+		loop_length as f64
+	    } else {
+		settings.delay
+	    }.round().max(1.0) as isize;
+	    settings.window = settings.delay.min(max_window);
+	}
 	
 	// -----------------------------------------------------------
 
-	let dry_wet = (*ports[6].unwrap_control(), *ports[7].unwrap_control());
+	if self.fresh {
+	    for channel in self.channels.iter_mut() {
+		channel.reset_parameters();
+	    }
+	    self.reversed = match direction {
+		Direction::Forward => 0.0,
+		Direction::Reverse => 1.0,
+	    };
+	    self.fresh = false;
+	}
 	
 	// -----------------------------------------------------------
-
-	let buffer_read_idx = (self.buf_idx + self.buf.len() - delay.0,
-			       self.buf_idx + self.buf.len() - delay.1);
-	let buf_len = self.buf.len();
+	// Stages without any effect are skipped for the whole block.
+	
+	for channel in self.channels.iter_mut() {
+	    let settings = &mut channel.settings;
+	    
+	    // Switching the saturation on again starts from silence
+	    // rather than from whatever it last saw.
+	    if drive.curve == Curve::Off {
+		channel.saturator.reset();
+	    }
+	    settings.modulated = !channel.lfo.is_idle(settings.modulation_depth);
+	    if !settings.modulated {
+		channel.lfo.skip(modulation_step, settings.modulation_phase, sample_count);
+	    }
+	}
+	
+	// The loop is only read while frozen or fading in or out of it.
+	let looping = freeze || self.freeze > 0.0;
+	
+	// Without ducking the envelope is not followed at all and starts
+	// over once it is turned on.
+	let ducked = ducking.is_active();
+	if !ducked {
+	    self.envelope.reset();
+	    self.ducking_gain = [1.0; CHUNK_SIZE];
+	}
+	
+	// -----------------------------------------------------------
+	// With all the stages besides the delay itself neutral and the
+	// controls settled, which is the way the plain delay is
+	// commonly used, the whole block is processed at once.
+	
+	let plain = interpolation == Interpolation::None &&
+	    drive.curve == Curve::Off &&
+	    tone.is_neutral() &&
+	    !ping_pong && !looping && !ducked &&
+	    direction == Direction::Forward && self.reversed == 0.0 &&
+	    stereo_mode == StereoMode::LeftRight && width == 1.0 &&
+	    self.channels.iter().all(Channel::is_plain);
+	
+	if plain {
+	    for (ch, channel) in self.channels.iter_mut().enumerate() {
+		channel.run_plain(&ports[ch].unwrap_audio()[..sample_count],
+				  &mut ports[CHANNELS + ch].unwrap_audio_mut()[..sample_count],
+				  mix_law, &tone);
+	    }
+	    return;
+	}
 	
 	// -----------------------------------------------------------
 
-	for ii in 0..sample_count {
+	// The delay lines are read a chunk at a time. A chunk is never
+	// longer than the shortest delay, so everything read within it
+	// was written before it started. The reverse heads reach back to
+	// the most recent sample, which takes a sample by sample walk.
+	let reverse_heads = direction == Direction::Reverse || self.reversed > 0.0;
+	let mut start = 0;
+	
+	while start < sample_count {
+	    let shortest = self.channels.iter()
+		.map(|channel| channel.shortest_delay(reverse_heads))
+		.fold(f64::INFINITY, f64::min);
+	    // Interpolating reads one sample closer than the integer
+	    // part of the delay.
+	    let len = ((shortest - 1.0).max(1.0) as usize)
+		.min(CHUNK_SIZE)
+		.min(sample_count - start);
+	    let end = start + len;
 	    
 	    // -------------------------------------------------------
-	    // Read in a sample
-	    let input_sample = (input.0[ii], input.1[ii]);
+	    // Read in the input.
+	    for (ch, channel) in self.channels.iter_mut().enumerate() {
+		channel.input[..len]
+		    .copy_from_slice(&ports[ch].unwrap_audio()[start..end]);
+	    }
+	    
+	    // The loudest channel drives the ducking of all of them,
+	    // keeping the stereo image intact. Its level is taken from the
+	    // input as it arrives, before any mid/side encoding.
+	    if ducked {
+		for ii in 0..len {
+		    let level = self.channels.iter()
+			.map(|channel| finite_or_zero(channel.input[ii].abs()))
+			.fold(0.0, Data::max);
+		    self.ducking_gain[ii] = self.envelope.next(level, &ducking);
+		}
+	    }
+	    
+	    if stereo_mode == StereoMode::MidSide {
+		let [left, right] = &mut self.channels;
+		stereo::encode(&mut left.input[..len], &mut right.input[..len]);
+	    }
+	    
+	    // In ping-pong mode all inputs are summed into the first
+	    // channel, so the echoes start there no matter where the
+	    // sound comes from.
+	    let mut mono = [0.0; CHUNK_SIZE];
+	    if ping_pong {
+		for (ii, mono) in mono[..len].iter_mut().enumerate() {
+		    *mono = self.channels.iter().map(|channel| channel.input[ii]).sum::<Data>() /
+			CHANNELS as Data;
+		}
+	    }
+	    
+	    // Weights of the loop and of the reverse heads for each
+	    // sample of the chunk.
+	    let mut looped = [0.0; CHUNK_SIZE];
+	    let mut reversed = [0.0; CHUNK_SIZE];
+	    for ii in 0..len {
+		self.freeze = if freeze {
+		    (self.freeze + freeze_step).min(1.0)
+		} else {
+		    (self.freeze - freeze_step).max(0.0)
+		};
+		self.reversed = match direction {
+		    Direction::Forward => (self.reversed - direction_step).max(0.0),
+		    Direction::Reverse => (self.reversed + direction_step).min(1.0),
+		};
+		looped[ii] = self.freeze;
+		reversed[ii] = self.reversed;
+	    }
 	    
 	    // -------------------------------------------------------
-	    // Calculate the stereo output.
-	    output.0[ii] = input_sample.0 * (1.0 - dry_wet.0) +
-		dry_wet.0 * self.buf[(buffer_read_idx.0 + ii) % buf_len].0;
-	    output.1[ii] = input_sample.1 * (1.0 - dry_wet.1) +
-		dry_wet.1 * self.buf[(buffer_read_idx.1 + ii) % buf_len].1;
+	    // Read the delay lines.
+	    for channel in self.channels.iter_mut() {
+		let Channel { buf, read_head, reverse, lfo, filter, saturator, settings,
+			      delayed, .. } = channel;
+		
+		for ii in 0..len {
+		    // Nothing has been written so far, the write position is
+		    // still the one at the start of the chunk.
+		    let offset = ii as isize;
+		    let modulation = if settings.modulated {
+			lfo.next(waveform, modulation_step, settings.modulation_depth,
+				 settings.modulation_phase, &glide)
+		    } else {
+			0.0
+		    };
+		    
+		    // Only the heads with a weight above 0 are read, so
+		    // the forward one is left alone in reverse mode and vice
+		    // versa.
+		    let forward = if reversed[ii] < 1.0 {
+			read_head.read(settings.delay, modulation, &glide, interpolation,
+				       |kk| buf.read(kk - offset))
+		    } else {
+			0.0
+		    };
+		    let backward = if reversed[ii] > 0.0 {
+			reverse.read(settings.window, modulation, &glide, interpolation,
+				     |kk| buf.read(kk - offset))
+		    } else {
+			0.0
+		    };
+		    let delayed_sample = forward + reversed[ii] * (backward - forward);
+		    
+		    delayed[ii] = saturator.process(filter.process(delayed_sample, &tone),
+						    &drive, &self.halfband);
+		}
+	    }
 	    
 	    // -------------------------------------------------------
-	    // Store the sample in the buffer.
-	    self.buf[(ii + self.buf_idx) % buf_len] = input_sample;
+	    // Store the samples in the buffers. The delayed signals are
+	    // fed back into the lines to produce the repeats. When frozen,
+	    // the buffer is fed from itself instead, repeating its last
+	    // loop_length samples. Non-finite input is dropped since it
+	    // would otherwise be recirculated forever, and so are the tails
+	    // of the repeats once they have decayed below audibility.
+	    for ch in 0..CHANNELS {
+		let previous = (ch + CHANNELS - 1) % CHANNELS;
+		let cross_delayed = self.channels[previous].delayed;
+		let channel = &mut self.channels[ch];
 		
+		for ii in 0..len {
+		    let input_sample = match (ping_pong, ch) {
+			(false, _) => channel.input[ii],
+			(true, 0) => mono[ii],
+			(true, _) => 0.0,
+		    };
+		    let feedback = channel.feedback.next(channel.settings.feedback, &glide);
+		    let cross_feedback = channel.cross_feedback.next(
+			channel.settings.cross_feedback, &glide);
+		    let sample = input_sample +
+			feedback * channel.delayed[ii] +
+			cross_feedback * cross_delayed[ii];
+		    let sample = if looping {
+			let loop_sample = channel.buf.read(channel.settings.loop_length);
+			(1.0 - looped[ii]) * sample + looped[ii] * loop_sample
+		    } else {
+			sample
+		    };
+		    
+		    channel.buf.write(flush_denormal(finite_or_zero(sample)));
+		}
+	    }
+	    
 	    // -------------------------------------------------------
-
+	    // Calculate the output. The feedback is done with the delayed
+	    // signal, so it may be widened now without affecting the
+	    // repeats. At a width of 1 it is left alone to keep the
+	    // signal bit-exact.
+	    let [left, right] = &mut self.channels;
+	    match stereo_mode {
+		StereoMode::MidSide => {
+		    for side in right.delayed[..len].iter_mut() {
+			*side *= width;
+		    }
+		},
+		StereoMode::LeftRight if width != 1.0 =>
+		    stereo::widen(&mut left.delayed[..len], &mut right.delayed[..len], width),
+		StereoMode::LeftRight => (),
+	    }
+	    
+	    for (ch, channel) in self.channels.iter_mut().enumerate() {
+		let mut output = ports[CHANNELS + ch].unwrap_audio_mut();
+		
+		channel.gains(len, mix_law, &glide);
+		channel.mix(&mut output[start..end], &self.ducking_gain);
+	    }
+	    
+	    if stereo_mode == StereoMode::MidSide {
+		stereo::decode(&mut ports[CHANNELS].unwrap_audio_mut()[start..end],
+			       &mut ports[CHANNELS + 1].unwrap_audio_mut()[start..end]);
+	    }
+	    
+	    start = end;
 	}
 	
 	// -----------------------------------------------------------
-	// Update the buffer index.
-	self.buf_idx += sample_count;
-	self.buf_idx %= buf_len;
-	
-	// -----------------------------------------------------------
 	
     }
 
@@ -96,80 +937,126 @@ impl Plugin for Delay {
 
 // -------------------------------------------------------------------
 
+// Restricts a control value to [lower, upper]. Values the host does
+// not have any business sending, like NaN, are mapped onto the lower
+// bound.
+fn limit(x: Data, lower: Data, upper: Data) -> Data {
+    if x > upper {
+	upper
+    } else if x > lower {
+	x
+    } else {
+	lower
+    }
+}
+
+// -------------------------------------------------------------------
+
+// State of a toggled control. As demanded by the LADSPA specification
+// all values above 0 switch it on.
+fn toggled(x: Data) -> bool {
+    x > 0.0
+}
+
+// -------------------------------------------------------------------
+
+fn finite_or_zero(x: Data) -> Data {
+    if x.is_finite() {
+	x
+    } else {
+	0.0
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Maps values too small to be audible onto 0. Decaying signals would
+// otherwise end up as subnormal numbers, which never decay any further
+// and slow down the arithmetic considerably.
+fn flush_denormal(x: Data) -> Data {
+    if x.abs() < DENORMAL_THRESHOLD {
+	0.0
+    } else {
+	x
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The ladspa crate hands the audio inputs to run() as shared slices
+// and the outputs as mutable ones. If the host connected an input and
+// an output to the same buffer they would alias, which is undefined
+// behaviour no matter in which order the samples are accessed. So
+// in-place processing has to be declared broken.
+fn properties() -> ladspa::Properties {
+    ladspa::PROP_HARD_REALTIME_CAPABLE | ladspa::PROP_INPLACE_BROKEN
+}
+
+// -------------------------------------------------------------------
+
 #[no_mangle]
 pub fn get_ladspa_descriptor(index: u64) -> Option<PluginDescriptor> {
-    match index {
-	0 => {
-	    Some(PluginDescriptor {
-		unique_id: 400,
-		label: "rust_delay_5s_stereo",
-		properties: ladspa::PROP_NONE,
-		name: "LADSPA Stereo Delay example in Rust",
-		maker: "thegreatwhiteshark",
-		copyright: "None",
-		ports: vec![
-		    Port {
-			name: "Left Audio In",
-			desc: PortDescriptor::AudioInput,
-			..Default::default()
-		    },
-		    Port {
-			name: "Right Audio In",
-			desc: PortDescriptor::AudioInput,
-			..Default::default()
-		    },
-		    Port {
-			name: "Left Audio Out",
-			desc: PortDescriptor::AudioOutput,
-			..Default::default()
-		    },
-		    Port {
-			name: "Right Audio Out",
-			desc: PortDescriptor::AudioOutput,
-			..Default::default()
-		    },
-		    Port {
-			name: "Left Delay (seconds)",
-			desc: PortDescriptor::ControlInput,
-			hint: None,
-			default: Some(DefaultValue::Value1),
-			lower_bound: Some(0.0),
-			upper_bound: Some(MAX_DELAY),
-		    },
-		    Port {
-			name: "Right Delay (seconds)",
-			desc: PortDescriptor::ControlInput,
-			hint: None,
-			default: Some(DefaultValue::Value1),
-			lower_bound: Some(0.0),
-			upper_bound: Some(MAX_DELAY),
-		    },
-		    Port {
-			name: "Left Dry/Wet",
-			desc: PortDescriptor::ControlInput,
-			hint: None,
-			default: Some(DefaultValue::Middle),
-			lower_bound: Some(0.0),
-			upper_bound: Some(1.0),
-		    },
-		    Port {
-			name: "Right Dry/Wet",
-			desc: PortDescriptor::ControlInput,
-			hint: None,
-			default: Some(DefaultValue::Middle),
-			lower_bound: Some(0.0),
-			upper_bound: Some(1.0),
-		    },
-		],
-		new: new_delay,
-
-	    })
+    let index = index as usize;
+    
+    // The multi-tap delay follows the family of plain delays.
+    if let Some(variant) = VARIANTS.get(index) {
+	Some(PluginDescriptor {
+	    unique_id: variant.unique_id,
+	    label: variant.label,
+	    properties: properties(),
+	    name: variant.name,
+	    maker: "thegreatwhiteshark",
+	    copyright: "None",
+	    ports: ports(variant.max_delay),
+	    new: new_delay,
+	})
+    } else if index == VARIANTS.len() {
+	Some(PluginDescriptor {
+	    unique_id: multi_tap::UNIQUE_ID,
+	    label: multi_tap::LABEL,
+	    properties: properties(),
+	    name: multi_tap::NAME,
+	    maker: "thegreatwhiteshark",
+	    copyright: "None",
+	    ports: multi_tap::ports(),
+	    new: multi_tap::new_multi_tap,
+	})
+    } else {
+	None
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn ports(max_delay: Data) -> Vec<Port> {
+    let mut ports = vec![
+	Port {
+	    name: "Left Audio In",
+	    desc: PortDescriptor::AudioInput,
+	    ..Default::default()
 	},
-	
-	// -----------------------------------------------------------
-	
-	_ => None
+	Port {
+	    name: "Right Audio In",
+	    desc: PortDescriptor::AudioInput,
+	    ..Default::default()
+	},
+	Port {
+	    name: "Left Audio Out",
+	    desc: PortDescriptor::AudioOutput,
+	    ..Default::default()
+	},
+	Port {
+	    name: "Right Audio Out",
+	    desc: PortDescriptor::AudioOutput,
+	    ..Default::default()
+	},
+    ];
+    
+    for control in CONTROLS.iter() {
+	ports.extend(control.ports(max_delay));
     }
+    
+    ports
 }
 
 // -------------------------------------------------------------------
@@ -0,0 +1,70 @@
+// -------------------------------------------------------------------
+// Combining the input with the delayed signal.
+//
+// The dry/wet control blends linearly by default, which makes the
+// mix a few dB quieter in the middle of its range than at either
+// end. The equal-power law keeps the level constant instead, and the
+// dry and wet signal may also be given levels of their own.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// Range of the separate dry and wet levels (in dB). The minimum mutes
+// the signal.
+pub const MIN_LEVEL: Data = -60.0;
+pub const MAX_LEVEL: Data = 12.0;
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MixLaw {
+    Linear,
+    EqualPower,
+    // The dry/wet control is ignored in favour of the dry and wet
+    // levels.
+    Separate,
+}
+
+impl MixLaw {
+
+    // ---------------------------------------------------------------
+
+    // Maps the value of the integer control port onto a law.
+    pub fn from_control(x: Data) -> MixLaw {
+	match x.round() as i32 {
+	    1 => MixLaw::EqualPower,
+	    2 => MixLaw::Separate,
+	    _ => MixLaw::Linear,
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Gains of the dry and the wet signal for the given position of
+    // the dry/wet control and linear levels.
+    pub fn gains(self, dry_wet: Data, dry_level: Data, wet_level: Data) -> (Data, Data) {
+	match self {
+	    MixLaw::Linear => (1.0 - dry_wet, dry_wet),
+	    // Sines on both sides, so either end is exact.
+	    MixLaw::EqualPower =>
+		(((1.0 - dry_wet) * std::f32::consts::FRAC_PI_2).sin(),
+		 (dry_wet * std::f32::consts::FRAC_PI_2).sin()),
+	    MixLaw::Separate => (dry_level, wet_level),
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Linear gain of a level in dB.
+pub fn level(x: Data) -> Data {
+    if x > MIN_LEVEL {
+	(10.0 as Data).powf(x / 20.0)
+    } else {
+	0.0
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,344 @@
+// -------------------------------------------------------------------
+// Delay with several taps reading a single delay line.
+//
+// Both inputs are summed into one buffer. Every tap reads it at a
+// time of its own and is placed in the stereo field using its gain
+// and pan, which allows for rhythmic patterns of echoes within a
+// single plugin instance. The sum of all taps is fed back into the
+// line, repeating the whole pattern.
+// -------------------------------------------------------------------
+
+use ladspa::{Data, DefaultValue, Plugin, PluginDescriptor, Port, PortConnection,
+	     PortDescriptor};
+use std::default::Default;
+
+use crate::channel::CHUNK_SIZE;
+use crate::interpolation::Interpolation;
+use crate::read_head::ReadHead;
+use crate::ring_buffer::RingBuffer;
+use crate::smoothing::{Glide, Smoother};
+use crate::{finite_or_zero, flush_denormal, limit, BUFFER_MARGIN, MAX_FEEDBACK, MAX_SMOOTHING};
+
+// -------------------------------------------------------------------
+
+pub const UNIQUE_ID: u64 = 404;
+pub const LABEL: &str = "rust_multi_tap_5s_stereo";
+pub const NAME: &str = "LADSPA Stereo Multi-Tap Delay example in Rust (5 s)";
+
+// Maximum time of a tap (in seconds).
+const MAX_DELAY: Data = 5.0;
+
+const TAP_COUNT: usize = 8;
+
+// -------------------------------------------------------------------
+
+// Layout of the ports. The two audio inputs and outputs are followed
+// by the controls of each tap and finally the ones shared by all of
+// them.
+const AUDIO_PORTS: usize = 4;
+
+const TIME_CONTROL: usize = 0;
+const GAIN_CONTROL: usize = 1;
+const PAN_CONTROL: usize = 2;
+const TAP_CONTROLS: usize = 3;
+
+const DRY_WET_CONTROL: usize = 0;
+const FEEDBACK_CONTROL: usize = 1;
+const INTERPOLATION_CONTROL: usize = 2;
+const SMOOTHING_CONTROL: usize = 3;
+
+// Names of the time, gain and pan control of each tap.
+const TAP_NAMES: [[&str; TAP_CONTROLS]; TAP_COUNT] = [
+    ["Tap 1 Time (seconds)", "Tap 1 Gain", "Tap 1 Pan"],
+    ["Tap 2 Time (seconds)", "Tap 2 Gain", "Tap 2 Pan"],
+    ["Tap 3 Time (seconds)", "Tap 3 Gain", "Tap 3 Pan"],
+    ["Tap 4 Time (seconds)", "Tap 4 Gain", "Tap 4 Pan"],
+    ["Tap 5 Time (seconds)", "Tap 5 Gain", "Tap 5 Pan"],
+    ["Tap 6 Time (seconds)", "Tap 6 Gain", "Tap 6 Pan"],
+    ["Tap 7 Time (seconds)", "Tap 7 Gain", "Tap 7 Pan"],
+    ["Tap 8 Time (seconds)", "Tap 8 Gain", "Tap 8 Pan"],
+];
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, Default)]
+struct Tap {
+    read_head: ReadHead,
+    // Gain of the tap in the feedback path and in either output.
+    gain: Smoother,
+    left: Smoother,
+    right: Smoother,
+    // Targets of the current block. The delay is in samples.
+    delay: f64,
+    target_gain: Data,
+    target_left: Data,
+    target_right: Data,
+}
+
+impl Tap {
+
+    // ---------------------------------------------------------------
+
+    fn reset_parameters(&mut self) {
+	self.read_head.reset(self.delay);
+	self.gain.reset(self.target_gain);
+	self.left.reset(self.target_left);
+	self.right.reset(self.target_right);
+    }
+}
+
+// -------------------------------------------------------------------
+
+struct MultiTap {
+    sample_rate: Data,
+    buf: RingBuffer<Data>,
+    taps: [Tap; TAP_COUNT],
+    dry_wet: Smoother,
+    // Set in activate() to let the smoothed parameters start right at
+    // the values of the first run().
+    fresh: bool,
+    // Input, sum of the taps, and wet gain of the current chunk for
+    // the left and right channel.
+    input: [[Data; CHUNK_SIZE]; 2],
+    delayed: [[Data; CHUNK_SIZE]; 2],
+    wet: [Data; CHUNK_SIZE],
+}
+
+// -------------------------------------------------------------------
+
+pub fn new_multi_tap(_: &PluginDescriptor, sample_rate: u64) -> Box<dyn Plugin + Send> {
+    Box::new(MultiTap::new(sample_rate as Data))
+}
+
+// -------------------------------------------------------------------
+
+impl MultiTap {
+
+    // ---------------------------------------------------------------
+
+    // All memory required by the delay is allocated right here, so
+    // neither activate() nor run() have to.
+    fn new(sample_rate: Data) -> MultiTap {
+	MultiTap {
+	    sample_rate,
+	    buf: RingBuffer::new((sample_rate * MAX_DELAY) as usize + BUFFER_MARGIN),
+	    taps: [Tap::default(); TAP_COUNT],
+	    dry_wet: Smoother::default(),
+	    fresh: true,
+	    input: [[0.0; CHUNK_SIZE]; 2],
+	    delayed: [[0.0; CHUNK_SIZE]; 2],
+	    wet: [0.0; CHUNK_SIZE],
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn tap_control<'a>(ports: &[&'a PortConnection<'a>], tap: usize, control: usize) -> Data {
+    *ports[AUDIO_PORTS + TAP_CONTROLS * tap + control].unwrap_control()
+}
+
+fn shared_control<'a>(ports: &[&'a PortConnection<'a>], control: usize) -> Data {
+    *ports[AUDIO_PORTS + TAP_CONTROLS * TAP_COUNT + control].unwrap_control()
+}
+
+// -------------------------------------------------------------------
+
+impl Plugin for MultiTap {
+
+    // ---------------------------------------------------------------
+
+    fn activate(&mut self) {
+	self.buf.clear();
+	self.fresh = true;
+    }
+
+    // ---------------------------------------------------------------
+
+    fn run<'a>(&mut self, sample_count: usize, ports: &[&'a PortConnection<'a>]) {
+	let interpolation = Interpolation::from_control(
+	    shared_control(ports, INTERPOLATION_CONTROL));
+	let glide = Glide::new(
+	    limit(shared_control(ports, SMOOTHING_CONTROL), 0.0, MAX_SMOOTHING),
+	    self.sample_rate);
+	let dry_wet = limit(shared_control(ports, DRY_WET_CONTROL), 0.0, 1.0);
+	let min_delay = interpolation.min_delay();
+	
+	for (ii, tap) in self.taps.iter_mut().enumerate() {
+	    let time = limit(tap_control(ports, ii, TIME_CONTROL), 0.0, MAX_DELAY);
+	    let gain = limit(tap_control(ports, ii, GAIN_CONTROL), 0.0, 1.0);
+	    let pan = limit(tap_control(ports, ii, PAN_CONTROL), -1.0, 1.0);
+	    
+	    // Equal-power panning. A centered tap is attenuated by 3 dB
+	    // in either channel.
+	    let left = (0.5 * (1.0 - pan)).sqrt();
+	    let right = (0.5 * (1.0 + pan)).sqrt();
+	    
+	    tap.delay = (time as f64 * self.sample_rate as f64).max(min_delay);
+	    tap.target_gain = gain;
+	    tap.target_left = gain * left;
+	    tap.target_right = gain * right;
+	}
+	
+	// The gains of all taps add up in the feedback path. Their total
+	// must not push the loop gain beyond MAX_FEEDBACK.
+	let total = self.taps.iter().map(|tap| tap.target_gain).sum::<Data>();
+	let feedback = limit(shared_control(ports, FEEDBACK_CONTROL), 0.0, MAX_FEEDBACK) /
+	    total.max(1.0);
+
+	// -----------------------------------------------------------
+
+	if self.fresh {
+	    for tap in self.taps.iter_mut() {
+		tap.reset_parameters();
+	    }
+	    self.dry_wet.reset(dry_wet);
+	    self.fresh = false;
+	}
+
+	// -----------------------------------------------------------
+
+	for start in (0..sample_count).step_by(CHUNK_SIZE) {
+	    let end = sample_count.min(start + CHUNK_SIZE);
+	    let len = end - start;
+
+	    // -------------------------------------------------------
+	    // Read in the input.
+	    for (ch, input) in self.input.iter_mut().enumerate() {
+		input[..len].copy_from_slice(&ports[ch].unwrap_audio()[start..end]);
+	    }
+
+	    // -------------------------------------------------------
+	    for ii in 0..len {
+		let MultiTap { buf, taps, delayed, .. } = self;
+		let mut left = 0.0;
+		let mut right = 0.0;
+		let mut repeats = 0.0;
+		
+		for tap in taps.iter_mut() {
+		    let delayed_sample = tap.read_head.read(
+			tap.delay, 0.0, &glide, interpolation, |kk| buf.read(kk));
+		    
+		    left += tap.left.next(tap.target_left, &glide) * delayed_sample;
+		    right += tap.right.next(tap.target_right, &glide) * delayed_sample;
+		    repeats += tap.gain.next(tap.target_gain, &glide) * delayed_sample;
+		}
+		
+		delayed[0][ii] = left;
+		delayed[1][ii] = right;
+		self.wet[ii] = self.dry_wet.next(dry_wet, &glide);
+		
+		let mono = 0.5 * (self.input[0][ii] + self.input[1][ii]);
+		self.buf.write(flush_denormal(finite_or_zero(mono + feedback * repeats)));
+	    }
+
+	    // -------------------------------------------------------
+	    // Calculate the output.
+	    for ch in 0..2 {
+		let mut output = ports[2 + ch].unwrap_audio_mut();
+		
+		for (((out, &dry), &delayed), &wet) in output[start..end].iter_mut()
+		    .zip(self.input[ch][..len].iter())
+		    .zip(self.delayed[ch][..len].iter())
+		    .zip(self.wet[..len].iter()) {
+			*out = dry * (1.0 - wet) + wet * delayed;
+		    }
+	    }
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+pub fn ports() -> Vec<Port> {
+    let mut ports = vec![
+	Port {
+	    name: "Left Audio In",
+	    desc: PortDescriptor::AudioInput,
+	    ..Default::default()
+	},
+	Port {
+	    name: "Right Audio In",
+	    desc: PortDescriptor::AudioInput,
+	    ..Default::default()
+	},
+	Port {
+	    name: "Left Audio Out",
+	    desc: PortDescriptor::AudioOutput,
+	    ..Default::default()
+	},
+	Port {
+	    name: "Right Audio Out",
+	    desc: PortDescriptor::AudioOutput,
+	    ..Default::default()
+	},
+    ];
+    
+    // Only the first tap is audible by default, making the plugin
+    // sound like a plain delay until further taps are turned up.
+    for (ii, &[time, gain, pan]) in TAP_NAMES.iter().enumerate() {
+	ports.push(Port {
+	    name: time,
+	    desc: PortDescriptor::ControlInput,
+	    hint: None,
+	    default: Some(DefaultValue::Value1),
+	    lower_bound: Some(0.0),
+	    upper_bound: Some(MAX_DELAY),
+	});
+	ports.push(Port {
+	    name: gain,
+	    desc: PortDescriptor::ControlInput,
+	    hint: None,
+	    default: Some(if ii == 0 { DefaultValue::Maximum } else { DefaultValue::Minimum }),
+	    lower_bound: Some(0.0),
+	    upper_bound: Some(1.0),
+	});
+	ports.push(Port {
+	    name: pan,
+	    desc: PortDescriptor::ControlInput,
+	    hint: None,
+	    default: Some(DefaultValue::Middle),
+	    lower_bound: Some(-1.0),
+	    upper_bound: Some(1.0),
+	});
+    }
+    
+    ports.extend(vec![
+	Port {
+	    name: "Dry/Wet",
+	    desc: PortDescriptor::ControlInput,
+	    hint: None,
+	    default: Some(DefaultValue::Middle),
+	    lower_bound: Some(0.0),
+	    upper_bound: Some(1.0),
+	},
+	Port {
+	    name: "Feedback",
+	    desc: PortDescriptor::ControlInput,
+	    hint: None,
+	    default: Some(DefaultValue::Minimum),
+	    lower_bound: Some(0.0),
+	    upper_bound: Some(MAX_FEEDBACK),
+	},
+	Port {
+	    name: "Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)",
+	    desc: PortDescriptor::ControlInput,
+	    hint: Some(ladspa::HINT_INTEGER),
+	    default: Some(DefaultValue::Value1),
+	    lower_bound: Some(0.0),
+	    upper_bound: Some(3.0),
+	},
+	Port {
+	    name: "Smoothing Time (seconds)",
+	    desc: PortDescriptor::ControlInput,
+	    hint: None,
+	    default: Some(DefaultValue::Low),
+	    lower_bound: Some(0.0),
+	    upper_bound: Some(MAX_SMOOTHING),
+	},
+    ]);
+    
+    ports
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,129 @@
+// -------------------------------------------------------------------
+// Read access to a single channel of the delay line.
+//
+// The read head follows changes of the delay time smoothly. Small
+// changes are glided, resulting in the typical pitch bend of a tape
+// delay, while large jumps are handled by cross-fading between the
+// old and the new position.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+use crate::interpolation::{Interpolation, Allpass};
+use crate::smoothing::{Glide, Smoother};
+
+// -------------------------------------------------------------------
+
+// `S` is the state carried from one sample to the next by the way the
+// buffer is read, the allpass interpolator for a plain read head.
+#[derive(Copy, Clone, Default)]
+pub struct ReadHead<S = Allpass> {
+    // Current delay in samples.
+    delay: Smoother<f64>,
+    state: S,
+    // Position of the read head being faded out.
+    old_delay: f64,
+    old_state: S,
+    // Gain of the current read head during a cross-fade. 1 if no
+    // fade is in progress.
+    fade: Data,
+}
+
+impl<S: Copy + Default> ReadHead<S> {
+
+    // ---------------------------------------------------------------
+
+    pub fn reset(&mut self, delay: f64) {
+	self.delay.reset(delay);
+	self.state = S::default();
+	self.old_state = S::default();
+	self.fade = 1.0;
+    }
+
+    // ---------------------------------------------------------------
+
+    // Shortest delay read at on the way to `target`. The smoothed
+    // delay approaches the target without overshooting it, and a
+    // cross-fade keeps reading at the old delay.
+    pub fn shortest_delay(&self, target: f64) -> f64 {
+	let delay = self.delay.value().min(target);
+	
+	if self.fade < 1.0 {
+	    delay.min(self.old_delay)
+	} else {
+	    delay
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Whether the read head has reached `target` without any
+    // cross-fade left, so it keeps reading right there.
+    pub fn is_settled(&self, target: f64) -> bool {
+	self.delay.value() == target && self.fade >= 1.0
+    }
+
+    // ---------------------------------------------------------------
+
+    // Reads the next sample for a delay of `target` samples.
+    // `read(delay, state)` has to return the sample at the smoothed
+    // delay.
+    pub fn read_with<R>(&mut self, target: f64, glide: &Glide, read: R) -> Data
+    where R: Fn(f64, &mut S) -> Data {
+	if self.fade >= 1.0 &&
+	    (target - self.delay.value()).abs() > glide.jump as f64 {
+		self.old_delay = self.delay.value();
+		self.old_state = self.state;
+		self.delay.reset(target);
+		self.fade = 0.0;
+	    }
+
+	// -----------------------------------------------------------
+
+	let delayed = read(self.delay.next(target, glide), &mut self.state);
+	
+	if self.fade >= 1.0 {
+	    return delayed;
+	}
+
+	// -----------------------------------------------------------
+
+	self.fade = (self.fade + glide.fade_step).min(1.0);
+	let old_delayed = read(self.old_delay, &mut self.old_state);
+	
+	old_delayed + self.fade * (delayed - old_delayed)
+    }
+}
+
+impl ReadHead {
+
+    // ---------------------------------------------------------------
+
+    // Reads the next sample for a delay of `target` samples, offset by
+    // `modulation` samples. `sample(k)` has to return the sample
+    // written `k` steps before the current write position.
+    pub fn read<F>(&mut self, target: f64, modulation: Data, glide: &Glide,
+		   interpolation: Interpolation, sample: F) -> Data
+    where F: Fn(isize) -> Data {
+	self.read_with(target, glide, |delay, allpass| {
+	    tap(delay + modulation as f64, interpolation, allpass, &sample)
+	})
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Reads the sample `delay` samples in the past. The position is only
+// split into the whole samples and the remaining fraction right here,
+// so the fraction is not lost for long delays.
+pub fn tap<F>(delay: f64, interpolation: Interpolation, allpass: &mut Allpass,
+	      sample: &F) -> Data
+where F: Fn(isize) -> Data {
+    // The modulation must not move the read head past the samples
+    // written so far.
+    let (whole, frac) = interpolation.split(delay.max(interpolation.min_delay()));
+    
+    interpolation.read(|kk| sample(whole as isize + kk), frac, allpass)
+}
+
+// -------------------------------------------------------------------
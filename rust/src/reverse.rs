@@ -0,0 +1,118 @@
+// -------------------------------------------------------------------
+// Reading the delay line backwards.
+//
+// Every window of the delay time is played back reversed. Doing so
+// takes two read heads, or grains, moving backwards through the
+// buffer at the speed the write position moves forwards. Each one
+// restarts at the most recent sample once it has swept a whole
+// window, at which point it reaches back twice the window. This is
+// why the windows are limited to half the maximum delay. The grains
+// are half a window apart and faded in and out using a Hann window,
+// so the jumps at the window boundaries happen while a grain is
+// silent and the gains of both always add up to one.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+use crate::interpolation::{Allpass, Interpolation};
+use crate::read_head::{tap, ReadHead};
+use crate::smoothing::Glide;
+
+// -------------------------------------------------------------------
+
+const GRAINS: usize = 2;
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Direction {
+    Forward,
+    Reverse,
+}
+
+impl Direction {
+
+    // ---------------------------------------------------------------
+
+    // Maps the value of the integer control port onto a direction.
+    pub fn from_control(x: Data) -> Direction {
+	match x.round() as i32 {
+	    1 => Direction::Reverse,
+	    _ => Direction::Forward,
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The length of the windows follows the delay time the same way a
+// forward read head does, so changing it glides or cross-fades between
+// two sets of grains.
+#[derive(Copy, Clone, Default)]
+pub struct Reverse {
+    head: ReadHead<Grains>,
+}
+
+impl Reverse {
+
+    // ---------------------------------------------------------------
+
+    pub fn reset(&mut self, window: f64) {
+	self.head.reset(window);
+    }
+
+    // ---------------------------------------------------------------
+
+    // Reads the next sample for windows of `target` samples. See
+    // `ReadHead::read()` for the remaining arguments.
+    pub fn read<F>(&mut self, target: f64, modulation: Data, glide: &Glide,
+		   interpolation: Interpolation, sample: F) -> Data
+    where F: Fn(isize) -> Data {
+	self.head.read_with(target, glide, |window, grains| {
+	    grains.read(window, modulation, interpolation, &sample)
+	})
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, Default)]
+struct Grains {
+    // Position within the window of the first grain. Kept in double
+    // precision, which is required to stay in sync with the write
+    // position for windows of many seconds.
+    phase: f64,
+    allpass: [Allpass; GRAINS],
+}
+
+impl Grains {
+
+    // ---------------------------------------------------------------
+
+    fn read<F>(&mut self, window: f64, modulation: Data,
+	       interpolation: Interpolation, sample: &F) -> Data
+    where F: Fn(isize) -> Data {
+	let mut delayed = 0.0;
+	
+	for (ii, allpass) in self.allpass.iter_mut().enumerate() {
+	    let phase = wrap(self.phase + ii as f64 / GRAINS as f64);
+	    let gain = (std::f32::consts::PI * phase as Data).sin().powi(2);
+	    
+	    delayed += gain * tap(2.0 * phase * window + modulation as f64, interpolation,
+				  allpass, sample);
+	}
+	
+	self.phase = wrap(self.phase + 1.0 / window);
+	
+	delayed
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Position within a window, i.e. the fractional part.
+fn wrap(phase: f64) -> f64 {
+    phase - phase.floor()
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,95 @@
+// -------------------------------------------------------------------
+// Fixed size ring buffer holding the history of a delay line.
+//
+// Just like in the C implementation the size is rounded up to a power
+// of two. This way wrapping around the end of the buffer is a
+// bitwise and instead of a comparably expensive modulo operation.
+// -------------------------------------------------------------------
+
+// -------------------------------------------------------------------
+
+pub struct RingBuffer<T> {
+    buf: Vec<T>,
+    // Length of the buffer minus one.
+    mask: usize,
+    // Position the next sample will be written to.
+    write_idx: usize,
+}
+
+impl<T: Copy + Default> RingBuffer<T> {
+
+    // ---------------------------------------------------------------
+
+    // Creates a buffer able to hold at least `min_len` samples. This
+    // is the only place memory is allocated.
+    pub fn new(min_len: usize) -> RingBuffer<T> {
+	let len = min_len.next_power_of_two();
+	
+	RingBuffer {
+	    buf: vec![T::default(); len],
+	    mask: len - 1,
+	    write_idx: 0,
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    // Discards the content of the buffer.
+    pub fn clear(&mut self) {
+	for x in self.buf.iter_mut() {
+	    *x = T::default();
+	}
+	self.write_idx = 0;
+    }
+
+    // ---------------------------------------------------------------
+
+    // Returns the sample written `delay` steps ago. A delay of 1
+    // corresponds to the most recent sample.
+    #[inline]
+    pub fn read(&self, delay: isize) -> T {
+	self.buf[self.write_idx.wrapping_sub(delay as usize) & self.mask]
+    }
+
+    // ---------------------------------------------------------------
+
+    // Appends a sample, overwriting the oldest one.
+    #[inline]
+    pub fn write(&mut self, value: T) {
+	self.buf[self.write_idx] = value;
+	self.write_idx = (self.write_idx + 1) & self.mask;
+    }
+
+    // ---------------------------------------------------------------
+
+    // Fills `out` with the samples starting `delay` steps ago, like
+    // successive calls to read() in between writes would. `out` must
+    // not be longer than `delay`.
+    pub fn read_into(&self, delay: usize, out: &mut [T]) {
+	let start = self.write_idx.wrapping_sub(delay) & self.mask;
+	let first = out.len().min(self.buf.len() - start);
+	let (head, tail) = out.split_at_mut(first);
+	
+	head.copy_from_slice(&self.buf[start..start + first]);
+	tail.copy_from_slice(&self.buf[..tail.len()]);
+    }
+
+    // ---------------------------------------------------------------
+
+    // Appends `map(x)` for all samples `x` of `values`.
+    pub fn write_from<F>(&mut self, values: &[T], map: F)
+    where F: Fn(T) -> T {
+	let first = values.len().min(self.buf.len() - self.write_idx);
+	let (head, tail) = values.split_at(first);
+	
+	for (x, &value) in self.buf[self.write_idx..].iter_mut().zip(head.iter()) {
+	    *x = map(value);
+	}
+	for (x, &value) in self.buf.iter_mut().zip(tail.iter()) {
+	    *x = map(value);
+	}
+	self.write_idx = (self.write_idx + values.len()) & self.mask;
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,228 @@
+// -------------------------------------------------------------------
+// Soft clipping of the delayed signal.
+//
+// With high feedback the repeats pile up. Instead of letting them
+// grow until the host clips them digitally, they are driven into one
+// of a couple of smooth transfer curves. Since every repeat passes
+// the stage once more, it also adds to the character of the repeats.
+//
+// The curves are applied at twice the sample rate. The signal is
+// upsampled and downsampled using a halfband lowpass, which keeps
+// most of the harmonics generated by the curves from folding back
+// into the audible range.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// Number of non-zero taps of the halfband filter besides its center
+// tap, half of them on either side.
+const TAPS: usize = 16;
+
+// Delay (in samples at the original rate) introduced by upsampling
+// and downsampling.
+pub const LATENCY: usize = TAPS - 1;
+
+// Amount of the previous output fed back into the tape curve. It is
+// responsible for the hysteresis and the slight loss of treble.
+const TAPE_MEMORY: Data = 0.3;
+
+// Offset of the positive half of the tube curve making it asymmetric.
+const TUBE_BIAS: Data = 0.3;
+
+// Cutoff (in Hz) of the highpass removing the offset the asymmetric
+// tube curve adds to every repeat.
+const DC_CUTOFF: Data = 2.0;
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Curve {
+    Off,
+    Tanh,
+    // Biased tanh for the positive half-waves and plain tanh for the
+    // negative ones, clipping them differently.
+    Tube,
+    // Tanh with memory, approximating the hysteresis of magnetic tape.
+    Tape,
+}
+
+impl Curve {
+
+    // ---------------------------------------------------------------
+
+    // Maps the value of the integer control port onto a curve.
+    pub fn from_control(x: Data) -> Curve {
+	match x.round() as i32 {
+	    1 => Curve::Tanh,
+	    2 => Curve::Tube,
+	    3 => Curve::Tape,
+	    _ => Curve::Off,
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Coefficients of the polyphase halfband filter. The center tap is
+// 0.5 and every other tap is zero, leaving only the ones below.
+#[derive(Copy, Clone)]
+pub struct Halfband {
+    taps: [Data; TAPS],
+}
+
+impl Halfband {
+
+    // ---------------------------------------------------------------
+
+    // Blackman windowed sinc.
+    pub fn new() -> Halfband {
+	let mut taps = [0.0; TAPS];
+	let half = TAPS as f64;
+	
+	for (ii, tap) in taps.iter_mut().enumerate() {
+	    // Odd distance from the center tap.
+	    let distance = 2.0 * ii as f64 - (TAPS - 1) as f64;
+	    let x = std::f64::consts::PI * distance;
+	    let window = 0.42 + 0.5 * (x / half).cos() + 0.08 * (2.0 * x / half).cos();
+	    
+	    *tap = ((x / 2.0).sin() / x * window) as Data;
+	}
+	
+	// Each polyphase branch has to have unity gain at DC.
+	let sum: Data = taps.iter().sum();
+	for tap in taps.iter_mut() {
+	    *tap *= 0.5 / sum;
+	}
+	
+	Halfband { taps }
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Per block settings shared by the saturation stages of all channels.
+#[derive(Copy, Clone)]
+pub struct Drive {
+    pub curve: Curve,
+    // Linear gain in front of the curve.
+    pub gain: Data,
+    // Pole of the highpass following the tube curve.
+    dc_pole: Data,
+}
+
+impl Drive {
+
+    // ---------------------------------------------------------------
+
+    pub fn new(curve: Curve, gain: Data, sample_rate: Data) -> Drive {
+	let dc_pole = (-2.0 * std::f32::consts::PI * DC_CUTOFF / sample_rate).exp();
+	
+	Drive { curve, gain, dc_pole }
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Saturation stage of a single channel.
+#[derive(Copy, Clone, Default)]
+pub struct Saturator {
+    // Most recent samples first. The input at the original rate and
+    // the even and odd samples of the output at twice the rate.
+    input: [Data; TAPS],
+    even: [Data; TAPS],
+    odd: [Data; TAPS],
+    // Last output of the tape curve.
+    tape: Data,
+    // Last input and output of the highpass after the tube curve.
+    dc_input: Data,
+    dc_output: Data,
+}
+
+impl Saturator {
+
+    // ---------------------------------------------------------------
+
+    pub fn reset(&mut self) {
+	*self = Saturator::default();
+    }
+
+    // ---------------------------------------------------------------
+
+    // With the curve off the signal passes straight through and the
+    // history is left alone. It is up to the caller to reset it.
+    pub fn process(&mut self, x: Data, drive: &Drive, halfband: &Halfband) -> Data {
+	if drive.curve == Curve::Off {
+	    return x;
+	}
+	
+	push(&mut self.input, x);
+
+	// -----------------------------------------------------------
+	// Upsample. The odd phase of the halfband filter is the center
+	// tap only, i.e. a pure delay.
+	let even = 2.0 * dot(&halfband.taps, &self.input);
+	let odd = self.input[TAPS / 2 - 1];
+	
+	let even = self.saturate(even, drive);
+	let odd = self.saturate(odd, drive);
+	push(&mut self.even, even);
+	push(&mut self.odd, odd);
+
+	// -----------------------------------------------------------
+	// Downsample, keeping the even samples.
+	let y = dot(&halfband.taps, &self.even) + 0.5 * self.odd[TAPS / 2];
+	if drive.curve != Curve::Tube {
+	    return y;
+	}
+
+	// -----------------------------------------------------------
+	// Rectifying the repeats over and over, the tube curve would
+	// otherwise let an offset build up in the feedback loop.
+	self.dc_output = y - self.dc_input + drive.dc_pole * self.dc_output;
+	self.dc_input = y;
+	self.dc_output
+    }
+
+    // ---------------------------------------------------------------
+
+    // The curves have unity gain for small signals and never amplify,
+    // so the drive only determines how soon they start to saturate.
+    fn saturate(&mut self, x: Data, drive: &Drive) -> Data {
+	let x = drive.gain * x;
+	let y = match drive.curve {
+	    Curve::Off => x,
+	    Curve::Tanh => x.tanh(),
+	    // Dividing by the slope at zero gives the biased tanh unity
+	    // gain for small signals, but below zero its slope is even
+	    // steeper. The negative half-waves therefore take the plain
+	    // tanh, so the curve never amplifies and high feedback cannot
+	    // build up an offset.
+	    Curve::Tube if x < 0.0 => x.tanh(),
+	    Curve::Tube => {
+		let slope = 1.0 - TUBE_BIAS.tanh().powi(2);
+		((x + TUBE_BIAS).tanh() - TUBE_BIAS.tanh()) / slope
+	    },
+	    Curve::Tape => {
+		self.tape = (x + TAPE_MEMORY * self.tape).tanh();
+		(1.0 - TAPE_MEMORY) * self.tape
+	    },
+	};
+	
+	y / drive.gain
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn push(history: &mut [Data; TAPS], x: Data) {
+    history.copy_within(..TAPS - 1, 1);
+    history[0] = x;
+}
+
+fn dot(a: &[Data; TAPS], b: &[Data; TAPS]) -> Data {
+    a.iter().zip(b.iter()).map(|(a, b)| a * b).sum()
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,128 @@
+// -------------------------------------------------------------------
+// Click-free handling of control values.
+//
+// Hosts update control ports once per block and often in large
+// steps. Applying these values directly results in audible clicks,
+// which is why they are approached gradually instead.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+use std::ops::{Add, Mul, Sub};
+
+use crate::{flush_denormal, DENORMAL_THRESHOLD};
+
+// -------------------------------------------------------------------
+
+// Delay changes larger than this (in seconds) are not glided but
+// cross-faded, since gliding over such a distance would result in a
+// prominent pitch shift.
+const JUMP_THRESHOLD: Data = 0.05;
+
+// -------------------------------------------------------------------
+
+// Per block settings shared by all smoothers of a plugin instance.
+#[derive(Copy, Clone)]
+pub struct Glide {
+    // Coefficient of the one-pole lowpass.
+    pub coefficient: Data,
+    // Increment of the cross-fade gain per sample. 1 results in an
+    // instant jump.
+    pub fade_step: Data,
+    // Smallest jump in the delay (in samples) triggering a
+    // cross-fade.
+    pub jump: Data,
+}
+
+impl Glide {
+
+    // ---------------------------------------------------------------
+
+    // `time` is the time constant of the smoothing in seconds and is
+    // also used as the length of the cross-fades.
+    pub fn new(time: Data, sample_rate: Data) -> Glide {
+	let samples = time * sample_rate;
+	
+	if samples >= 1.0 {
+	    Glide {
+		coefficient: (-1.0 / samples).exp(),
+		fade_step: 1.0 / samples,
+		jump: JUMP_THRESHOLD * sample_rate,
+	    }
+	} else {
+	    Glide {
+		coefficient: 0.0,
+		fade_step: 1.0,
+		jump: JUMP_THRESHOLD * sample_rate,
+	    }
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Types of smoothed values. Gains do fine in single precision, while
+// delays (in samples) are kept in double precision, which is able to
+// resolve fractions of a sample even for delays of several minutes.
+pub trait Value: Copy + Default + PartialEq + From<Data>
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    // The value, or 0 if it is too small to be audible.
+    fn flush_denormal(self) -> Self;
+}
+
+impl Value for Data {
+    fn flush_denormal(self) -> Data {
+	flush_denormal(self)
+    }
+}
+
+impl Value for f64 {
+    fn flush_denormal(self) -> f64 {
+	if self.abs() < DENORMAL_THRESHOLD as f64 {
+	    0.0
+	} else {
+	    self
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// One-pole lowpass moving a value towards its target.
+#[derive(Copy, Clone, Default)]
+pub struct Smoother<T = Data> {
+    value: T,
+}
+
+impl<T: Value> Smoother<T> {
+
+    // ---------------------------------------------------------------
+
+    // Sets the value without any smoothing.
+    pub fn reset(&mut self, value: T) {
+	self.value = value;
+    }
+
+    // ---------------------------------------------------------------
+
+    pub fn value(&self) -> T {
+	self.value
+    }
+
+    // ---------------------------------------------------------------
+
+    // Close to the target the steps get smaller than the resolution
+    // of the value, which would keep it hanging right next to the
+    // target forever, so it is set to the target right away. Once
+    // there, nothing is left to compute.
+    pub fn next(&mut self, target: T, glide: &Glide) -> T {
+	if self.value != target {
+	    let offset = T::from(glide.coefficient) * (self.value - target);
+	    let value = target + offset.flush_denormal();
+	    self.value = if value == self.value { target } else { value };
+	}
+	
+	self.value
+    }
+}
+
+// -------------------------------------------------------------------
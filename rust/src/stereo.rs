@@ -0,0 +1,73 @@
+// -------------------------------------------------------------------
+// Mid/side processing of a pair of channels.
+//
+// Instead of left and right, the delay may process the sum (mid) and
+// the difference (side) of both channels. Delaying them separately
+// keeps the echoes mono-compatible, since whatever happens to the
+// side signal cancels out when the channels are summed.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum StereoMode {
+    LeftRight,
+    MidSide,
+}
+
+impl StereoMode {
+
+    // ---------------------------------------------------------------
+
+    // Maps the value of the integer control port onto a mode.
+    pub fn from_control(x: Data) -> StereoMode {
+	match x.round() as i32 {
+	    1 => StereoMode::MidSide,
+	    _ => StereoMode::LeftRight,
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Turns left and right into mid and side, in place.
+pub fn encode(left: &mut [Data], right: &mut [Data]) {
+    for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+	let mid = 0.5 * (*left + *right);
+	let side = 0.5 * (*left - *right);
+	
+	*left = mid;
+	*right = side;
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Turns mid and side back into left and right, in place.
+pub fn decode(mid: &mut [Data], side: &mut [Data]) {
+    for (mid, side) in mid.iter_mut().zip(side.iter_mut()) {
+	let left = *mid + *side;
+	let right = *mid - *side;
+	
+	*mid = left;
+	*side = right;
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Scales the side part of a pair of channels by `width`, in place. 0
+// collapses them to mono, values above 1 exaggerate their difference.
+pub fn widen(left: &mut [Data], right: &mut [Data], width: Data) {
+    for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+	let mid = 0.5 * (*left + *right);
+	let side = 0.5 * width * (*left - *right);
+	
+	*left = mid + side;
+	*right = mid - side;
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,30 @@
+// -------------------------------------------------------------------
+// Delay times given as note values relative to a tempo.
+// -------------------------------------------------------------------
+
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// Index of the shortest note value, a 1/64 note. The value with index
+// n is a 1/2^n note, so the middle one is a 1/8 note.
+pub const SHORTEST_NOTE_VALUE: i32 = 6;
+
+// -------------------------------------------------------------------
+
+// Length in seconds of the note described by the value and modifier
+// controls at a tempo given in beats (quarter notes) per minute.
+pub fn note_length(tempo: Data, value: Data, modifier: Data) -> Data {
+    // Length relative to a whole note. Out-of-range values are mapped
+    // onto the nearest valid one.
+    let whole_notes = (0.5 as Data).powi((value.round() as i32).clamp(0, SHORTEST_NOTE_VALUE));
+    let whole_notes = match modifier.round() as i32 {
+	1 => whole_notes * 1.5,
+	2 => whole_notes * 2.0 / 3.0,
+	_ => whole_notes,
+    };
+    
+    whole_notes * 4.0 * 60.0 / tempo
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,51 @@
+// -------------------------------------------------------------------
+// Hosts are allowed to call activate() and run() from the audio
+// thread. Make sure neither of them touches the heap.
+// -------------------------------------------------------------------
+
+extern crate ladspa;
+extern crate rust_delay_5s_stereo;
+
+mod common;
+mod realtime;
+
+use common::{connect, controls, descriptor, test_signal, SAMPLE_RATE};
+use ladspa::PortConnection;
+
+// -------------------------------------------------------------------
+
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    let violations = realtime::check(f);
+    violations.allocations + violations.deallocations
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn activate_does_not_allocate() {
+    let desc = descriptor(0);
+    let mut plugin = (desc.new)(&desc, SAMPLE_RATE);
+    
+    assert_eq!(count_allocations(|| plugin.activate()), 0);
+    assert_eq!(count_allocations(|| plugin.deactivate()), 0);
+    assert_eq!(count_allocations(|| plugin.activate()), 0);
+}
+
+#[test]
+fn run_does_not_allocate() {
+    let desc = descriptor(0);
+    let controls = controls(&desc, &[("Left Feedback", 0.5),
+				     ("Right Feedback", 0.5)]);
+    let input = test_signal(1.0);
+    let mut output = (vec![0.0; input.0.len()], vec![0.0; input.1.len()]);
+    let mut plugin = (desc.new)(&desc, SAMPLE_RATE);
+    plugin.activate();
+    
+    let connections = connect(&desc, &controls, (&input.0, &input.1),
+			      (&mut output.0, &mut output.1));
+    let ports: Vec<&PortConnection> = connections.iter().collect();
+    
+    assert_eq!(count_allocations(|| plugin.run(input.0.len(), &ports)), 0);
+}
+
+// -------------------------------------------------------------------
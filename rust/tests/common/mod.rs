@@ -0,0 +1,347 @@
+// -------------------------------------------------------------------
+// Helpers driving the plugins of this crate through the `Plugin`
+// trait, the same way the ladspa crate does on behalf of the host.
+// -------------------------------------------------------------------
+
+#![allow(dead_code)]
+
+use ladspa::{Data, DefaultValue, PluginDescriptor, Port, PortConnection,
+	     PortData, PortDescriptor};
+use rust_delay_5s_stereo::get_ladspa_descriptor;
+use ladspa_host::Library;
+use std::cell::RefCell;
+use std::env;
+
+// -------------------------------------------------------------------
+
+pub const SAMPLE_RATE: u64 = 44100;
+
+pub const INTERPOLATION: &str = "Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)";
+
+// -------------------------------------------------------------------
+
+pub fn descriptor(index: u64) -> PluginDescriptor {
+    get_ladspa_descriptor(index).expect("plugin not found")
+}
+
+// -------------------------------------------------------------------
+
+// The shared object built from this crate. Cargo puts it next to the
+// test executables.
+pub fn library() -> Library {
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    
+    unsafe { Library::load(deps.join("librust_delay_5s_stereo.so")) }
+	.unwrap_or_else(|err| panic!("{}", err))
+}
+
+// -------------------------------------------------------------------
+
+fn has_hint(port: &Port, hint: ladspa::ControlHint) -> bool {
+    port.hint.is_some_and(|hints| hints.contains(hint))
+}
+
+// Bounds of a port as seen by the host, i.e. scaled by the sample
+// rate if the port asks for it.
+pub fn lower_bound(port: &Port) -> Option<Data> {
+    port.lower_bound.map(|bound| scale(port, bound))
+}
+
+pub fn upper_bound(port: &Port) -> Option<Data> {
+    port.upper_bound.map(|bound| scale(port, bound))
+}
+
+fn scale(port: &Port, bound: Data) -> Data {
+    if has_hint(port, ladspa::HINT_SAMPLE_RATE) {
+	bound * SAMPLE_RATE as Data
+    } else {
+	bound
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Value a host would assign to a control port it was not told to
+// change.
+pub fn default_value(port: &Port) -> Data {
+    let lower = lower_bound(port).unwrap_or(0.0);
+    let upper = upper_bound(port).unwrap_or(0.0);
+    let has = |hint| has_hint(port, hint);
+    let between = |weight: Data| {
+	if has(ladspa::HINT_LOGARITHMIC) && lower > 0.0 && upper > 0.0 {
+	    (lower.ln() * (1.0 - weight) + upper.ln() * weight).exp()
+	} else {
+	    lower * (1.0 - weight) + upper * weight
+	}
+    };
+    
+    let value = match port.default {
+	Some(DefaultValue::Minimum) => lower,
+	Some(DefaultValue::Low) => between(0.25),
+	Some(DefaultValue::Middle) => between(0.5),
+	Some(DefaultValue::High) => between(0.75),
+	Some(DefaultValue::Maximum) => upper,
+	Some(DefaultValue::Value1) => 1.0,
+	Some(DefaultValue::Value100) => 100.0,
+	Some(DefaultValue::Value440) => 440.0,
+	Some(DefaultValue::Value0) | None => 0.0,
+    };
+    
+    if has(ladspa::HINT_INTEGER) {
+	value.round()
+    } else {
+	value
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Control values of all control input ports of the plugin, in port
+// order, with `changes` (port name, value) applied on top of the
+// defaults. A port set more than once takes the last value, the same
+// as `host::Plugin::controls()`.
+pub fn controls(desc: &PluginDescriptor, changes: &[(&str, Data)]) -> Vec<Data> {
+    for &(name, _) in changes {
+	assert!(desc.ports.iter().any(|port| port.name == name),
+		"unknown port {}", name);
+    }
+    
+    desc.ports.iter()
+	.filter(|port| matches!(port.desc, PortDescriptor::ControlInput))
+	.map(|port| changes.iter()
+	     .rev()
+	     .find(|&&(name, _)| name == port.name)
+	     .map(|&(_, value)| value)
+	     .unwrap_or_else(|| default_value(port)))
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+// Connects the ports of the plugin the way the ladspa crate does
+// before calling `Plugin::run()`.
+pub fn connect<'a>(desc: &PluginDescriptor, controls: &'a [Data],
+		   input: (&'a [Data], &'a [Data]),
+		   output: (&'a mut [Data], &'a mut [Data]))
+		   -> Vec<PortConnection<'a>> {
+    let mut audio_in = vec![input.0, input.1].into_iter();
+    let mut audio_out = vec![output.0, output.1].into_iter();
+    let mut control_in = controls.iter();
+    
+    desc.ports.iter()
+	.map(|port| PortConnection {
+	    port: *port,
+	    data: match port.desc {
+		PortDescriptor::AudioInput =>
+		    PortData::AudioInput(audio_in.next().unwrap()),
+		PortDescriptor::AudioOutput =>
+		    PortData::AudioOutput(RefCell::new(audio_out.next().unwrap())),
+		PortDescriptor::ControlInput =>
+		    PortData::ControlInput(control_in.next().unwrap()),
+		_ => panic!("unsupported port {}", port.name),
+	    },
+	})
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+// Renders a stereo signal through a fresh instance of the plugin in
+// blocks of `block_size` samples.
+pub fn render(desc: &PluginDescriptor, controls: &[Data],
+	      input: (&[Data], &[Data]), block_size: usize)
+	      -> (Vec<Data>, Vec<Data>) {
+    let mut plugin = (desc.new)(desc, SAMPLE_RATE);
+    plugin.activate();
+    
+    let mut output = (vec![0.0; input.0.len()], vec![0.0; input.1.len()]);
+    
+    for start in (0..input.0.len()).step_by(block_size) {
+	let end = (start + block_size).min(input.0.len());
+	let connections = connect(desc, controls,
+				  (&input.0[start..end], &input.1[start..end]),
+				  (&mut output.0[start..end],
+				   &mut output.1[start..end]));
+	let ports: Vec<&PortConnection> = connections.iter().collect();
+	
+	plugin.run(end - start, &ports);
+    }
+    
+    plugin.deactivate();
+    
+    output
+}
+
+// -------------------------------------------------------------------
+
+// Like render(), but the control values change from `before` to
+// `after` between two blocks, at sample `switch`.
+pub fn render_switching(desc: &PluginDescriptor, before: &[Data], after: &[Data],
+			switch: usize, input: (&[Data], &[Data]), block_size: usize)
+			-> (Vec<Data>, Vec<Data>) {
+    assert_eq!(switch % block_size, 0, "switch between blocks");
+    let mut plugin = (desc.new)(desc, SAMPLE_RATE);
+    plugin.activate();
+    
+    let mut output = (vec![0.0; input.0.len()], vec![0.0; input.1.len()]);
+    
+    for start in (0..input.0.len()).step_by(block_size) {
+	let end = (start + block_size).min(input.0.len());
+	let controls = if start < switch { before } else { after };
+	let connections = connect(desc, controls,
+				  (&input.0[start..end], &input.1[start..end]),
+				  (&mut output.0[start..end],
+				   &mut output.1[start..end]));
+	let ports: Vec<&PortConnection> = connections.iter().collect();
+	
+	plugin.run(end - start, &ports);
+    }
+    
+    plugin.deactivate();
+    
+    output
+}
+
+// -------------------------------------------------------------------
+
+// The plugin and the control values the tests of a file start from.
+pub struct Fixture {
+    pub index: u64,
+    pub controls: &'static [(&'static str, Data)],
+}
+
+impl Fixture {
+
+    // ---------------------------------------------------------------
+
+    pub fn descriptor(&self) -> PluginDescriptor {
+	descriptor(self.index)
+    }
+
+    // ---------------------------------------------------------------
+
+    // Control values of the fixture with `changes` applied on top.
+    pub fn controls(&self, changes: &[(&str, Data)]) -> Vec<Data> {
+	controls(&self.descriptor(), &[self.controls, changes].concat())
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Delay of both channels of WET (in seconds).
+pub const WET_DELAY: Data = 0.1;
+
+// The fully wet delay without interpolation, which most of the tests
+// start from, changing just the controls they are about.
+pub const WET: Fixture = Fixture {
+    index: 0,
+    controls: &[
+	("Left Delay (seconds)", WET_DELAY),
+	("Right Delay (seconds)", WET_DELAY),
+	("Left Dry/Wet", 1.0),
+	("Right Dry/Wet", 1.0),
+	(INTERPOLATION, 0.0),
+    ],
+};
+
+// -------------------------------------------------------------------
+
+// Renders a stereo signal in blocks of 256 samples with the controls
+// of the fixture and `changes` on top.
+pub fn render_with(fixture: &Fixture, changes: &[(&str, Data)],
+		   input: (&[Data], &[Data])) -> (Vec<Data>, Vec<Data>) {
+    render(&fixture.descriptor(), &fixture.controls(changes), input, 256)
+}
+
+// -------------------------------------------------------------------
+
+// Largest step in the output of a sine when the controls change from
+// `before` to `after` at sample `switch`, from the last sample before
+// on.
+pub fn step_after_change(fixture: &Fixture, before: &[(&str, Data)],
+			 after: &[(&str, Data)], switch: usize, block_size: usize)
+			 -> Data {
+    let input = sine(switch as Data / SAMPLE_RATE as Data + 0.5);
+    let output = render_switching(&fixture.descriptor(), &fixture.controls(before),
+				  &fixture.controls(after), switch,
+				  (&input.0, &input.1), block_size);
+    
+    largest_step(&output.0[switch - 1..]).max(largest_step(&output.1[switch - 1..]))
+}
+
+// -------------------------------------------------------------------
+
+// A couple of seconds of a decaying, slightly detuned chord used as
+// test signal.
+pub fn test_signal(seconds: Data) -> (Vec<Data>, Vec<Data>) {
+    let length = (seconds * SAMPLE_RATE as Data) as usize;
+    let signal = |frequency: Data| -> Vec<Data> {
+	(0..length)
+	    .map(|ii| {
+		let time = ii as Data / SAMPLE_RATE as Data;
+		0.5 * (-3.0 * time).exp() *
+		    (2.0 * std::f32::consts::PI * frequency * time).sin()
+	    })
+	    .collect()
+    };
+    
+    (signal(220.0), signal(331.0))
+}
+
+// -------------------------------------------------------------------
+
+// A steady sine of 220 Hz.
+pub fn sine(seconds: Data) -> (Vec<Data>, Vec<Data>) {
+    let signal = (0..(seconds * SAMPLE_RATE as Data) as usize)
+	.map(|ii| {
+	    let time = ii as Data / SAMPLE_RATE as Data;
+	    0.5 * (2.0 * std::f32::consts::PI * 220.0 * time).sin()
+	})
+	.collect::<Vec<_>>();
+    
+    (signal.clone(), signal)
+}
+
+// -------------------------------------------------------------------
+
+pub fn impulse(seconds: Data) -> (Vec<Data>, Vec<Data>) {
+    let mut signal = vec![0.0; (seconds * SAMPLE_RATE as Data) as usize];
+    signal[0] = 1.0;
+    
+    (signal.clone(), signal)
+}
+
+// -------------------------------------------------------------------
+
+pub fn samples(seconds: Data) -> usize {
+    (seconds * SAMPLE_RATE as Data).round() as usize
+}
+
+// -------------------------------------------------------------------
+
+// Positions and values of all non-zero samples.
+pub fn echoes(signal: &[Data]) -> Vec<(usize, Data)> {
+    signal.iter().cloned().enumerate().filter(|&(_, x)| x != 0.0).collect()
+}
+
+pub fn echo_positions(signal: &[Data]) -> Vec<usize> {
+    echoes(signal).iter().map(|&(ii, _)| ii).collect()
+}
+
+// -------------------------------------------------------------------
+
+pub fn peak(signal: &[Data]) -> Data {
+    signal.iter().fold(0.0, |acc: Data, x| acc.max(x.abs()))
+}
+
+// Peak of the signal between the given times (in seconds).
+pub fn peak_between(signal: &[Data], start: Data, end: Data) -> Data {
+    peak(&signal[samples(start)..samples(end)])
+}
+
+// Largest difference between neighbouring samples.
+pub fn largest_step(signal: &[Data]) -> Data {
+    signal.windows(2).fold(0.0, |acc: Data, pair| acc.max((pair[1] - pair[0]).abs()))
+}
+
+// -------------------------------------------------------------------
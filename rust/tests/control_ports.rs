@@ -0,0 +1,341 @@
+// -------------------------------------------------------------------
+// The host is free to ignore the bounds of the control ports. Make
+// sure the delay neither crashes nor produces anything dangerous for
+// values it is not supposed to send.
+// -------------------------------------------------------------------
+
+extern crate ladspa;
+extern crate rust_delay_5s_stereo;
+
+mod common;
+
+use common::{impulse, peak, render_with, test_signal, Fixture};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// Block size used by render_with().
+const BLOCK_SIZE: usize = 256;
+
+// The delay with all controls at their defaults.
+const DEFAULTS: Fixture = Fixture { index: 0, controls: &[] };
+
+// -------------------------------------------------------------------
+
+fn assert_same_output(changes: &[(&str, Data)], expected: &[(&str, Data)]) {
+    let input = test_signal(1.0);
+    let input = (&input.0[..], &input.1[..]);
+    let output = render_with(&DEFAULTS, changes, input);
+    
+    assert!(output.0.iter().chain(output.1.iter()).all(|x| x.is_finite()));
+    assert_eq!(output, render_with(&DEFAULTS, expected, input));
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn nan_delay_is_treated_as_shortest_delay() {
+    assert_same_output(&[("Left Delay (seconds)", Data::NAN),
+			 ("Right Delay (seconds)", Data::NAN)],
+		       &[("Left Delay (seconds)", 0.0),
+			 ("Right Delay (seconds)", 0.0)]);
+}
+
+#[test]
+fn negative_delay_is_treated_as_shortest_delay() {
+    assert_same_output(&[("Left Delay (seconds)", -0.5),
+			 ("Right Delay (seconds)", Data::NEG_INFINITY)],
+		       &[("Left Delay (seconds)", 0.0),
+			 ("Right Delay (seconds)", 0.0)]);
+}
+
+#[test]
+fn delay_above_maximum_is_limited() {
+    assert_same_output(&[("Left Delay (seconds)", 60.0),
+			 ("Right Delay (seconds)", Data::INFINITY)],
+		       &[("Left Delay (seconds)", 5.0),
+			 ("Right Delay (seconds)", 5.0)]);
+}
+
+#[test]
+fn delay_at_maximum_is_read_with_every_interpolation() {
+    let input = test_signal(6.0);
+    
+    for mode in 0..4 {
+	let output = render_with(
+	    &DEFAULTS,
+	    &[("Left Delay (seconds)", 5.0),
+	      ("Right Delay (seconds)", 4.99999),
+	      ("Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)",
+	       mode as Data)],
+	    (&input.0, &input.1));
+	
+	assert!(output.0.iter().chain(output.1.iter()).all(|x| x.is_finite()));
+	assert!(peak(&output.0) <= 1.0 && peak(&output.1) <= 1.0);
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn nan_dry_wet_is_treated_as_dry() {
+    let input = test_signal(1.0);
+    let output = render_with(&DEFAULTS,
+			     &[("Left Dry/Wet", Data::NAN), ("Right Dry/Wet", Data::NAN)],
+			     (&input.0, &input.1));
+    
+    assert_eq!(output, input);
+}
+
+#[test]
+fn out_of_range_dry_wet_is_limited() {
+    assert_same_output(&[("Left Dry/Wet", 3.0),
+			 ("Right Dry/Wet", Data::INFINITY)],
+		       &[("Left Dry/Wet", 1.0),
+			 ("Right Dry/Wet", 1.0)]);
+    assert_same_output(&[("Left Dry/Wet", -3.0),
+			 ("Right Dry/Wet", Data::NEG_INFINITY)],
+		       &[("Left Dry/Wet", 0.0),
+			 ("Right Dry/Wet", 0.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn nan_feedback_is_treated_as_no_feedback() {
+    assert_same_output(&[("Left Feedback", Data::NAN), ("Right Feedback", Data::NAN)],
+		       &[("Left Feedback", 0.0), ("Right Feedback", 0.0)]);
+}
+
+#[test]
+fn excessive_feedback_still_decays() {
+    for &gain in &[1.0, 10.0, Data::INFINITY] {
+	let input = impulse(20.0);
+	let output = render_with(&DEFAULTS,
+				 &[("Left Delay (seconds)", 0.01),
+				   ("Right Delay (seconds)", 0.01),
+				   ("Left Dry/Wet", 1.0),
+				   ("Right Dry/Wet", 1.0),
+				   ("Left Feedback", gain),
+				   ("Right Feedback", Data::NEG_INFINITY)],
+				 (&input.0, &input.1));
+	let tail = output.0.len() - 4410;
+	
+	assert!(peak(&output.0) <= 1.0);
+	assert!(peak(&output.0[tail..]) < 1e-3);
+	assert!(peak(&output.1) <= 1.0);
+    }
+}
+
+#[test]
+fn excessive_cross_feedback_still_decays() {
+    let input = impulse(20.0);
+    let output = render_with(&DEFAULTS,
+			     &[("Left Delay (seconds)", 0.01),
+			       ("Right Delay (seconds)", 0.013),
+			       ("Left Dry/Wet", 1.0),
+			       ("Right Dry/Wet", 1.0),
+			       ("Left Feedback", 1.0),
+			       ("Right Feedback", 1.0),
+			       ("Left to Right Feedback", Data::INFINITY),
+			       ("Right to Left Feedback", 10.0)],
+			     (&input.0, &input.1));
+    let tail = output.0.len() - 4410;
+    
+    assert!(peak(&output.0) <= 1.0 && peak(&output.1) <= 1.0);
+    assert!(peak(&output.0[tail..]) < 1e-3 && peak(&output.1[tail..]) < 1e-3);
+}
+
+#[test]
+fn nan_ping_pong_is_treated_as_off() {
+    assert_same_output(&[("Left Feedback", 0.5), ("Ping-Pong", Data::NAN)],
+		       &[("Left Feedback", 0.5), ("Ping-Pong", 0.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_tempo_is_limited() {
+    assert_same_output(&[("Tempo Sync", 1.0), ("Tempo (BPM)", Data::NAN)],
+		       &[("Tempo Sync", 1.0), ("Tempo (BPM)", 20.0)]);
+    assert_same_output(&[("Tempo Sync", 1.0), ("Tempo (BPM)", 0.0)],
+		       &[("Tempo Sync", 1.0), ("Tempo (BPM)", 20.0)]);
+    assert_same_output(&[("Tempo Sync", 1.0), ("Tempo (BPM)", Data::INFINITY)],
+		       &[("Tempo Sync", 1.0), ("Tempo (BPM)", 220.0)]);
+}
+
+#[test]
+fn invalid_note_value_is_limited() {
+    let value = "Left Note Value (0: 1/1, 1: 1/2, 2: 1/4, 3: 1/8, 4: 1/16, 5: 1/32, 6: 1/64)";
+    
+    assert_same_output(&[("Tempo Sync", 1.0), (value, Data::NAN)],
+		       &[("Tempo Sync", 1.0), (value, 0.0)]);
+    assert_same_output(&[("Tempo Sync", 1.0), (value, -3.0)],
+		       &[("Tempo Sync", 1.0), (value, 0.0)]);
+    assert_same_output(&[("Tempo Sync", 1.0), (value, Data::INFINITY)],
+		       &[("Tempo Sync", 1.0), (value, 6.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn nan_modulation_depth_is_treated_as_none() {
+    assert_same_output(&[("Modulation Depth (seconds)", Data::NAN)],
+		       &[("Modulation Depth (seconds)", 0.0)]);
+}
+
+#[test]
+fn invalid_modulation_is_limited() {
+    let depth = ("Modulation Depth (seconds)", 0.005);
+    
+    assert_same_output(&[depth, ("Modulation Depth (seconds)", Data::INFINITY)],
+		       &[depth, ("Modulation Depth (seconds)", 0.02)]);
+    assert_same_output(&[depth, ("Modulation Rate (Hz)", Data::NAN)],
+		       &[depth, ("Modulation Rate (Hz)", 0.05)]);
+    assert_same_output(&[depth, ("Modulation Rate (Hz)", Data::INFINITY)],
+		       &[depth, ("Modulation Rate (Hz)", 20.0)]);
+    assert_same_output(&[depth, ("Modulation Stereo Phase (degrees)", Data::NAN)],
+		       &[depth, ("Modulation Stereo Phase (degrees)", 0.0)]);
+    assert_same_output(
+	&[depth, ("Modulation Waveform (0: sine, 1: triangle, 2: random)", Data::NAN)],
+	&[depth, ("Modulation Waveform (0: sine, 1: triangle, 2: random)", 0.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_cutoff_is_limited() {
+    let feedback = ("Left Feedback", 0.5);
+    
+    assert_same_output(&[feedback, ("Low-Pass Cutoff (Hz)", Data::NAN)],
+		       &[feedback, ("Low-Pass Cutoff (Hz)", 44.1)]);
+    assert_same_output(&[feedback, ("Low-Pass Cutoff (Hz)", Data::INFINITY)],
+		       &[feedback, ("Low-Pass Cutoff (Hz)", 22050.0)]);
+    assert_same_output(&[feedback, ("High-Pass Cutoff (Hz)", Data::NAN)],
+		       &[feedback, ("High-Pass Cutoff (Hz)", 0.0)]);
+    assert_same_output(&[feedback, ("High-Pass Cutoff (Hz)", Data::INFINITY)],
+		       &[feedback, ("High-Pass Cutoff (Hz)", 4410.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_saturation_is_limited() {
+    let curve = "Saturation (0: off, 1: tanh, 2: tube, 3: tape)";
+    
+    for &mode in &[Data::NAN, Data::INFINITY, -1.0, 4.0] {
+	assert_same_output(&[(curve, mode)], &[(curve, 0.0)]);
+    }
+    assert_same_output(&[(curve, 1.0), ("Saturation Drive (dB)", Data::NAN)],
+		       &[(curve, 1.0), ("Saturation Drive (dB)", 0.0)]);
+    assert_same_output(&[(curve, 1.0), ("Saturation Drive (dB)", Data::INFINITY)],
+		       &[(curve, 1.0), ("Saturation Drive (dB)", 24.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_freeze_is_limited() {
+    let length = "Freeze Loop Length (seconds, 0: delay time)";
+    
+    assert_same_output(&[("Freeze", Data::NAN)], &[("Freeze", 0.0)]);
+    assert_same_output(&[("Freeze", 1.0), (length, Data::NAN)],
+		       &[("Freeze", 1.0), (length, 0.0)]);
+    assert_same_output(&[("Freeze", 1.0), (length, Data::INFINITY)],
+		       &[("Freeze", 1.0), (length, 5.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_direction_is_treated_as_forward() {
+    let name = "Direction (0: forward, 1: reverse up to half the max delay)";
+    
+    for &direction in &[Data::NAN, Data::INFINITY, Data::NEG_INFINITY, -1.0, 2.0] {
+	assert_same_output(&[(name, direction)], &[(name, 0.0)]);
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_ducking_is_limited() {
+    let amount = ("Ducking Amount (dB)", 40.0);
+    
+    assert_same_output(&[("Ducking Amount (dB)", Data::NAN)],
+		       &[("Ducking Amount (dB)", 0.0)]);
+    assert_same_output(&[("Ducking Amount (dB)", Data::INFINITY)], &[amount]);
+    assert_same_output(&[amount, ("Ducking Threshold (dB)", Data::NAN)],
+		       &[amount, ("Ducking Threshold (dB)", -60.0)]);
+    assert_same_output(&[amount, ("Ducking Threshold (dB)", Data::INFINITY)],
+		       &[amount, ("Ducking Threshold (dB)", 0.0)]);
+    assert_same_output(&[amount, ("Ducking Attack (seconds)", Data::INFINITY),
+			 ("Ducking Release (seconds)", Data::NAN)],
+		       &[amount, ("Ducking Attack (seconds)", 0.1),
+			 ("Ducking Release (seconds)", 0.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_stereo_settings_are_limited() {
+    for &mode in &[Data::NAN, Data::INFINITY, Data::NEG_INFINITY, -1.0, 2.0] {
+	assert_same_output(&[("Stereo Mode (0: left/right, 1: mid/side)", mode)],
+			   &[("Stereo Mode (0: left/right, 1: mid/side)", 0.0)]);
+    }
+    assert_same_output(&[("Stereo Width", Data::NAN)], &[("Stereo Width", 0.0)]);
+    assert_same_output(&[("Stereo Width", Data::INFINITY)], &[("Stereo Width", 2.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_mix_settings_are_limited() {
+    let law = "Mix Law (0: linear, 1: equal-power, 2: separate levels)";
+    
+    for &mode in &[Data::NAN, Data::INFINITY, Data::NEG_INFINITY, -1.0, 3.0] {
+	assert_same_output(&[(law, mode)], &[(law, 0.0)]);
+    }
+    assert_same_output(&[(law, 2.0), ("Dry Level (dB)", Data::NAN),
+			 ("Wet Level (dB)", Data::INFINITY)],
+		       &[(law, 2.0), ("Dry Level (dB)", -60.0),
+			 ("Wet Level (dB)", 12.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn invalid_interpolation_is_treated_as_none() {
+    for &mode in &[Data::NAN, Data::INFINITY, Data::NEG_INFINITY, -1.0, 4.0] {
+	assert_same_output(
+	    &[("Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)", mode)],
+	    &[("Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)", 0.0)]);
+    }
+}
+
+#[test]
+fn invalid_smoothing_time_is_limited() {
+    assert_same_output(&[("Smoothing Time (seconds)", Data::NAN)],
+		       &[("Smoothing Time (seconds)", 0.0)]);
+    assert_same_output(&[("Smoothing Time (seconds)", Data::NEG_INFINITY)],
+		       &[("Smoothing Time (seconds)", 0.0)]);
+    assert_same_output(&[("Smoothing Time (seconds)", Data::INFINITY)],
+		       &[("Smoothing Time (seconds)", 0.2)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn non_finite_input_does_not_poison_the_delay_line() {
+    let mut input = test_signal(1.0);
+    input.0[10] = Data::NAN;
+    input.1[10] = Data::INFINITY;
+    let output = render_with(&DEFAULTS,
+			     &[("Left Feedback", 0.5), ("Right Feedback", 0.5)],
+			     (&input.0, &input.1));
+    
+    assert!(output.0[BLOCK_SIZE..].iter().all(|x| x.is_finite()));
+    assert!(output.1[BLOCK_SIZE..].iter().all(|x| x.is_finite()));
+}
+
+// -------------------------------------------------------------------
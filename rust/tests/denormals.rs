@@ -0,0 +1,60 @@
+// -------------------------------------------------------------------
+// Decaying signals have to reach 0 instead of lingering as subnormal
+// numbers, which would slow down the processing for good.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{impulse, peak_between, render_with, SAMPLE_RATE, WET};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// Changes to the wet delay making the repeats short and dense.
+const DENSE: [(&str, Data); 4] = [
+    ("Left Delay (seconds)", 0.01),
+    ("Right Delay (seconds)", 0.01),
+    ("Left Feedback", 0.9),
+    ("Right Feedback", 0.9),
+];
+
+// -------------------------------------------------------------------
+
+// Renders a single impulse followed by a minute of silence.
+fn render_tail(changes: &[(&str, Data)]) -> (Vec<Data>, Vec<Data>) {
+    let input = impulse(60.0);
+    
+    render_with(&WET, &[&DENSE[..], changes].concat(), (&input.0, &input.1))
+}
+
+// The last samples being silent and not just tiny.
+fn assert_silent_tail(signal: &[Data]) {
+    let tail = &signal[signal.len() - 10 * SAMPLE_RATE as usize..];
+    
+    assert!(tail.iter().all(|&x| x == 0.0),
+	    "{:?}", tail.iter().find(|&&x| x != 0.0));
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn repeats_decay_to_zero() {
+    let output = render_tail(&[]);
+    
+    for channel in [&output.0, &output.1].iter() {
+	assert!(peak_between(channel, 0.0, 0.02) > 0.8);
+	assert_silent_tail(channel);
+    }
+}
+
+#[test]
+fn filter_states_decay_to_zero() {
+    let output = render_tail(&[("Low-Pass Cutoff (Hz)", 2000.0),
+			       ("High-Pass Cutoff (Hz)", 200.0)]);
+    
+    for channel in [&output.0, &output.1].iter() {
+	assert_silent_tail(channel);
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,105 @@
+// -------------------------------------------------------------------
+// Attenuating the echoes while the input is loud.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{peak_between, render_with, samples, SAMPLE_RATE, WET};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const DELAY: Data = 0.25;
+
+// Changes to the wet delay repeating the phrase a couple of times.
+const REPEATED: [(&str, Data); 4] = [
+    ("Left Delay (seconds)", DELAY),
+    ("Right Delay (seconds)", DELAY),
+    ("Left Feedback", 0.5),
+    ("Right Feedback", 0.5),
+];
+
+// -------------------------------------------------------------------
+
+// Half a second of a tone followed by silence.
+fn phrase(seconds: Data) -> Vec<Data> {
+    (0..samples(seconds))
+	.map(|ii| {
+	    let time = ii as Data / SAMPLE_RATE as Data;
+	    if time < 0.5 {
+		0.5 * (2.0 * std::f32::consts::PI * 440.0 * time).sin()
+	    } else {
+		0.0
+	    }
+	})
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+fn render_phrase(changes: &[(&str, Data)]) -> Vec<Data> {
+    let input = phrase(1.5);
+    let output = render_with(&WET, &[&REPEATED[..], changes].concat(), (&input, &input));
+    
+    assert_eq!(output.0, output.1);
+    output.0
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn no_amount_leaves_echoes_untouched() {
+    let reference = render_phrase(&[]);
+    
+    for &threshold in [-60.0, -30.0, 0.0].iter() {
+	assert_eq!(render_phrase(&[("Ducking Threshold (dB)", threshold),
+				   ("Ducking Attack (seconds)", 0.0)]),
+		   reference);
+    }
+}
+
+#[test]
+fn echoes_are_ducked_while_input_is_loud() {
+    let reference = render_phrase(&[]);
+    let output = render_phrase(&[("Ducking Threshold (dB)", -30.0),
+				 ("Ducking Amount (dB)", 40.0),
+				 ("Ducking Attack (seconds)", 0.0),
+				 ("Ducking Release (seconds)", 0.01)]);
+    
+    // The input peaks at -6 dB, so the echoes are attenuated by 24 dB
+    // while it plays.
+    let ducked = peak_between(&output, 0.3, 0.5) / peak_between(&reference, 0.3, 0.5);
+    assert!((ducked - (10.0 as Data).powf(-24.0 / 20.0)).abs() < 0.01, "{}", ducked);
+    
+    // Once it is over, the repeats come back at full level.
+    assert_eq!(peak_between(&output, 0.7, 1.5), peak_between(&reference, 0.7, 1.5));
+}
+
+#[test]
+fn amount_limits_attenuation() {
+    let reference = render_phrase(&[]);
+    let output = render_phrase(&[("Ducking Threshold (dB)", -60.0),
+				 ("Ducking Amount (dB)", 12.0),
+				 ("Ducking Attack (seconds)", 0.0)]);
+    let ducked = peak_between(&output, 0.3, 0.5) / peak_between(&reference, 0.3, 0.5);
+    
+    assert!((ducked - (10.0 as Data).powf(-12.0 / 20.0)).abs() < 0.01, "{}", ducked);
+}
+
+#[test]
+fn release_lets_echoes_bloom_gradually() {
+    let output = render_phrase(&[("Ducking Threshold (dB)", -30.0),
+				 ("Ducking Amount (dB)", 40.0),
+				 ("Ducking Attack (seconds)", 0.0),
+				 ("Ducking Release (seconds)", 0.5)]);
+    let reference = render_phrase(&[]);
+    let recovery = |start: Data| {
+	peak_between(&output, start, start + 0.05) /
+	    peak_between(&reference, start, start + 0.05)
+    };
+    
+    assert!(recovery(0.55) < recovery(0.65));
+    assert!(recovery(0.65) < recovery(0.7));
+}
+
+// -------------------------------------------------------------------
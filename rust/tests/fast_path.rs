@@ -0,0 +1,71 @@
+// -------------------------------------------------------------------
+// Blocks in which the delay does nothing but delay and mix are
+// processed by a separate, faster path. It has to produce exactly the
+// same output as the sample by sample processing.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{descriptor, render_switching, samples, test_signal, INTERPOLATION};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// Both delays are whole multiples of the sampling period, so linear
+// interpolation reads the very same samples as none at all. It takes
+// the sample by sample path though.
+const DELAYS: [(&str, Data); 2] = [
+    ("Left Delay (seconds)", 0.25),
+    ("Right Delay (seconds)", 0.5),
+];
+
+// -------------------------------------------------------------------
+
+// Renders the test signal with the controls switching from `before`
+// to `after` halfway through, once for each interpolation.
+fn render_both(before: &[(&str, Data)], after: &[(&str, Data)], block_size: usize)
+	       -> [(Vec<Data>, Vec<Data>); 2] {
+    let desc = descriptor(0);
+    let input = test_signal(2.0);
+    let switch = samples(1.0) / block_size * block_size;
+    let render = |interpolation: Data| {
+	let controls = |changes: &[(&str, Data)]| common::controls(
+	    &desc, &[&DELAYS[..], changes, &[(INTERPOLATION, interpolation)]].concat());
+	
+	render_switching(&desc, &controls(before), &controls(after), switch,
+			 (&input.0, &input.1), block_size)
+    };
+    
+    [render(0.0), render(1.0)]
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn plain_blocks_match_sample_by_sample_processing() {
+    for &block_size in [1, 100, 256, 4096].iter() {
+	let [fast, reference] = render_both(&[("Left Dry/Wet", 0.3)],
+					    &[("Left Dry/Wet", 0.8)], block_size);
+	
+	assert!(fast == reference, "block size {}", block_size);
+    }
+}
+
+// After the feedback is turned off, the repeats already in the delay
+// lines are played back by the fast path once it has faded out. The
+// short smoothing time lets it fade out before the end of the signal.
+#[test]
+fn fast_path_takes_over_after_feedback() {
+    let smoothing = ("Smoothing Time (seconds)", 0.01);
+    
+    for &block_size in [100, 256].iter() {
+	let [fast, reference] = render_both(&[smoothing,
+					      ("Left Feedback", 0.6),
+					      ("Right Feedback", 0.4)],
+					    &[smoothing], block_size);
+	
+	assert!(fast == reference, "block size {}", block_size);
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,93 @@
+// -------------------------------------------------------------------
+// Freezing the buffer, looping its content instead of recording the
+// input.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{echo_positions, impulse, largest_step, render_switching, render_with, samples,
+	     test_signal, WET, WET_DELAY};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const DELAY: Data = WET_DELAY;
+
+const FREEZE: (&str, Data) = ("Freeze", 1.0);
+const RELEASE: (&str, Data) = ("Freeze", 0.0);
+
+// -------------------------------------------------------------------
+
+// Renders the input, switching the freeze control from `before` to
+// `after` at 0.5 s.
+fn render_freeze(input: (&[Data], &[Data]), before: (&str, Data), after: (&str, Data))
+		 -> (Vec<Data>, Vec<Data>) {
+    render_switching(&WET.descriptor(), &WET.controls(&[before]), &WET.controls(&[after]),
+		     samples(0.5), input, samples(0.01))
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn frozen_buffer_repeats_forever() {
+    let mut input = impulse(1.05);
+    // Arrives after the fade and must not be recorded anymore.
+    input.0[samples(0.5)] = 1.0;
+    input.1[samples(0.5)] = 1.0;
+    let output = render_with(&WET, &[FREEZE], (&input.0, &input.1));
+    let expected = (1..=10).map(|ii| samples(ii as Data * DELAY)).collect::<Vec<_>>();
+    
+    for channel in [&output.0, &output.1].iter() {
+	assert_eq!(echo_positions(channel), expected);
+	for &ii in expected.iter() {
+	    assert_eq!(channel[ii], channel[expected[0]]);
+	}
+	assert!(channel[expected[0]] > 0.99);
+    }
+}
+
+#[test]
+fn loop_length_overrides_delay_time() {
+    let input = impulse(1.0);
+    let output = render_with(&WET,
+			     &[FREEZE, ("Freeze Loop Length (seconds, 0: delay time)", 0.25)],
+			     (&input.0, &input.1));
+    
+    assert_eq!(echo_positions(&output.0),
+	       vec![samples(0.1), samples(0.35), samples(0.6), samples(0.85)]);
+}
+
+// Once frozen, the loop repeats sample for sample and the cross-fade
+// keeps its boundary free of clicks.
+#[test]
+fn loop_is_periodic_and_smooth() {
+    let input = test_signal(2.0);
+    let output = render_freeze((&input.0, &input.1), RELEASE, FREEZE);
+    let start = samples(0.5 + DELAY);
+    let period = samples(DELAY);
+    
+    for channel in [&output.0, &output.1].iter() {
+	for ii in start + period..channel.len() {
+	    assert_eq!(channel[ii], channel[ii - period], "sample {}", ii);
+	}
+	
+	let step = largest_step(&channel[start..]);
+	assert!(step < 0.05, "{}", step);
+    }
+}
+
+#[test]
+fn releasing_freeze_records_again() {
+    let mut input = impulse(1.5);
+    input.0[0] = 0.0;
+    input.1[0] = 0.0;
+    input.0[samples(1.0)] = 1.0;
+    input.1[samples(1.0)] = 1.0;
+    let output = render_freeze((&input.0, &input.1), FREEZE, RELEASE);
+    
+    for channel in [&output.0, &output.1].iter() {
+	assert_eq!(echo_positions(channel), vec![samples(1.0 + DELAY)]);
+    }
+}
+
+// -------------------------------------------------------------------
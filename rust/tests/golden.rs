@@ -0,0 +1,246 @@
+// -------------------------------------------------------------------
+// Regression tests rendering snare.wav through the delay.
+//
+// The outputs are compared against reference renderings checked in
+// below tests/golden. After an intended change of the sound, they
+// can be regenerated using
+//
+//     UPDATE_GOLDEN=1 cargo test --test golden
+//
+// In addition, the output is compared sample-for-sample against the
+// C implementation of the delay using the settings both of them
+// support.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::INTERPOLATION;
+use ladspa::Data;
+use ladspa_host::Library;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::OnceLock;
+
+// -------------------------------------------------------------------
+
+// Length of the renderings. The hit of the snare decays to below
+// -60 dB after 0.3 seconds, but the renderings have to be long enough
+// to hold the echo at the default delay of 1 second.
+const SNARE_SECONDS: Data = 2.0;
+
+const BLOCK_SIZE: usize = 256;
+
+// Maximum deviation from the reference renderings. Leaves some room
+// for differences in floating point code generation between compilers
+// and platforms.
+const TOLERANCE: Data = 1e-5;
+
+// -------------------------------------------------------------------
+
+// Control settings of the reference renderings, named after their
+// files.
+const SETTINGS: [(&str, &[(&str, Data)]); 4] = [
+    ("default", &[]),
+    ("feedback", &[("Left Delay (seconds)", 0.125),
+		   ("Right Delay (seconds)", 0.25),
+		   ("Left Dry/Wet", 0.4),
+		   ("Right Dry/Wet", 0.6),
+		   ("Left Feedback", 0.7),
+		   ("Right Feedback", 0.5),
+		   (INTERPOLATION, 0.0)]),
+    ("cubic", &[("Left Delay (seconds)", 0.0789),
+		("Right Delay (seconds)", 0.0321),
+		("Left Feedback", 0.8),
+		("Right Feedback", 0.3),
+		(INTERPOLATION, 2.0)]),
+    ("allpass", &[("Left Delay (seconds)", 0.0111),
+		  ("Right Delay (seconds)", 0.0222),
+		  ("Left Dry/Wet", 0.7),
+		  ("Right Dry/Wet", 0.7),
+		  ("Left Feedback", 0.6),
+		  ("Right Feedback", 0.6),
+		  (INTERPOLATION, 3.0)]),
+];
+
+// Delay and dry/wet of the left and right channel compared against
+// the C implementation, which has neither feedback nor interpolation.
+const C_SETTINGS: [(Data, Data, Data, Data); 4] = [
+    (1.0, 1.0, 0.5, 0.5),
+    (0.1, 0.2, 1.0, 1.0),
+    (0.0123, 0.3, 0.25, 0.8),
+    (0.75, 0.05, 0.0, 1.0),
+];
+
+// -------------------------------------------------------------------
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+// -------------------------------------------------------------------
+
+// The beginning of snare.wav, one buffer per channel, and its sample
+// rate.
+fn snare() -> (Vec<Vec<Data>>, u64) {
+    let (channels, sample_rate) = read_wav(&manifest_dir().join("../snare.wav"));
+    let length = (SNARE_SECONDS * sample_rate as Data) as usize;
+
+    (channels.into_iter()
+     .map(|mut channel| {
+	 channel.truncate(length);
+	 channel
+     })
+     .collect(),
+     sample_rate)
+}
+
+// -------------------------------------------------------------------
+
+fn read_wav(path: &PathBuf) -> (Vec<Vec<Data>>, u64) {
+    let mut reader = hound::WavReader::open(path)
+	.unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+    let spec = reader.spec();
+    let samples: Vec<Data> = match spec.sample_format {
+	hound::SampleFormat::Float =>
+	    reader.samples::<f32>().map(Result::unwrap).collect(),
+	hound::SampleFormat::Int => {
+	    let scale = (1u64 << (spec.bits_per_sample - 1)) as Data;
+	    reader.samples::<i32>().map(|x| x.unwrap() as Data / scale).collect()
+	},
+    };
+    let channels = (0..spec.channels as usize)
+	.map(|ch| samples.iter()
+	     .skip(ch)
+	     .step_by(spec.channels as usize)
+	     .cloned()
+	     .collect())
+	.collect();
+
+    (channels, spec.sample_rate as u64)
+}
+
+// -------------------------------------------------------------------
+
+fn write_wav(path: &PathBuf, channels: &[Vec<Data>], sample_rate: u64) {
+    let spec = hound::WavSpec {
+	channels: channels.len() as u16,
+	sample_rate: sample_rate as u32,
+	bits_per_sample: 32,
+	sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+
+    for ii in 0..channels[0].len() {
+	for channel in channels {
+	    writer.write_sample(channel[ii]).unwrap();
+	}
+    }
+    writer.finalize().unwrap();
+}
+
+// -------------------------------------------------------------------
+
+// Builds the C delay into the temporary directory of the tests, using
+// the compiler named by $CC or else `cc`.
+fn c_plugin() -> &'static PathBuf {
+    static PLUGIN: OnceLock<PathBuf> = OnceLock::new();
+
+    PLUGIN.get_or_init(|| {
+	let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("delay_stereo.so");
+	let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+	let status = Command::new(&compiler)
+	    .args(["-shared", "-fPIC", "-O2", "-Wall", "-Werror", "-o"])
+	    .arg(&path)
+	    .arg(manifest_dir().join("../c/delay_stereo.c"))
+	    .status()
+	    .unwrap_or_else(|err| panic!("running the C compiler {} failed: {} \
+					  (point $CC to a C compiler)", compiler, err));
+
+	assert!(status.success(), "compiling the C delay failed: {}", status);
+	path
+    })
+}
+
+// -------------------------------------------------------------------
+
+// Renders the snare through the Rust delay using the given control
+// changes.
+fn render(changes: &[(&str, Data)]) -> Vec<Vec<Data>> {
+    let library = common::library();
+    let plugin = library.plugin("rust_delay_5s_stereo").unwrap();
+    let (input, sample_rate) = snare();
+    let controls = plugin.controls(sample_rate, changes).unwrap();
+
+    plugin.render(sample_rate, &input, &controls, BLOCK_SIZE).unwrap()
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn matches_golden_files() {
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let (_, sample_rate) = snare();
+
+    for &(name, changes) in SETTINGS.iter() {
+	let path = manifest_dir().join("tests/golden").join(format!("{}.wav", name));
+	let output = render(changes);
+
+	if update {
+	    fs::create_dir_all(path.parent().unwrap()).unwrap();
+	    write_wav(&path, &output, sample_rate);
+	    continue;
+	}
+
+	let (reference, _) = read_wav(&path);
+
+	assert_eq!(output.len(), reference.len(), "{}: number of channels", name);
+	for (ch, (output, reference)) in output.iter().zip(reference.iter()).enumerate() {
+	    assert_eq!(output.len(), reference.len(), "{}: length of channel {}", name, ch);
+
+	    for (ii, (x, y)) in output.iter().zip(reference.iter()).enumerate() {
+		assert!((x - y).abs() <= TOLERANCE,
+			"{}: channel {} differs at sample {}: {} instead of {}",
+			name, ch, ii, x, y);
+	    }
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn matches_c_delay() {
+    let c_library = unsafe { Library::load(c_plugin()) }.unwrap();
+    let c_plugin = c_library.plugin("c_delay_5s_stereo").unwrap();
+    let (input, sample_rate) = snare();
+
+    for &(left_delay, right_delay, left_wet, right_wet) in C_SETTINGS.iter() {
+	let output = render(&[("Left Delay (seconds)", left_delay),
+			      ("Right Delay (seconds)", right_delay),
+			      ("Left Dry/Wet", left_wet),
+			      ("Right Dry/Wet", right_wet),
+			      ("Left Feedback", 0.0),
+			      ("Right Feedback", 0.0),
+			      (INTERPOLATION, 0.0)]);
+	let c_controls = c_plugin.controls(sample_rate, &[
+	    ("Delay (Seconds) (Left)", left_delay),
+	    ("Delay (Seconds) (Right)", right_delay),
+	    ("Dry/Wet Balance (Left)", left_wet),
+	    ("Dry/Wet Balance (Right)", right_wet),
+	]).unwrap();
+	let c_output = c_plugin.render(sample_rate, &input, &c_controls, BLOCK_SIZE)
+	    .unwrap();
+
+	for (ch, (output, c_output)) in output.iter().zip(c_output.iter()).enumerate() {
+	    if let Some(ii) = (0..output.len()).find(|&ii| output[ii] != c_output[ii]) {
+		panic!("{:?}: channel {} differs at sample {}: {} instead of {}",
+		       (left_delay, right_delay, left_wet, right_wet),
+		       ch, ii, output[ii], c_output[ii]);
+	    }
+	}
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,157 @@
+// -------------------------------------------------------------------
+// Drives the exported C ABI of the plugin library through the host
+// module, just like a real host would.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{SAMPLE_RATE, descriptor, default_value, library, lower_bound, upper_bound,
+	     impulse, test_signal};
+use ladspa::{Data, PortDescriptor};
+use ladspa_host::PortKind;
+
+// -------------------------------------------------------------------
+
+#[test]
+fn descriptors_are_valid() {
+    if let Err(err) = library().validate(SAMPLE_RATE) {
+	panic!("{}", err);
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The C descriptors have to describe the same plugins as the
+// `PluginDescriptor`s they are generated from.
+#[test]
+fn descriptors_match_plugin_descriptors() {
+    let library = library();
+    let plugins = library.plugins();
+    
+    assert!(!plugins.is_empty());
+    
+    for (index, plugin) in plugins.iter().enumerate() {
+	let desc = descriptor(index as u64);
+	
+	assert_eq!(plugin.unique_id(), desc.unique_id as _);
+	assert_eq!(plugin.label(), desc.label);
+	assert_eq!(plugin.name(), desc.name);
+	assert_eq!(plugin.maker(), desc.maker);
+	assert_eq!(plugin.ports().len(), desc.ports.len());
+	
+	for (info, port) in plugin.ports().iter().zip(desc.ports.iter()) {
+	    let kind = match port.desc {
+		PortDescriptor::AudioInput => PortKind::AudioInput,
+		PortDescriptor::AudioOutput => PortKind::AudioOutput,
+		PortDescriptor::ControlInput => PortKind::ControlInput,
+		PortDescriptor::ControlOutput => PortKind::ControlOutput,
+		PortDescriptor::Invalid => panic!("invalid port {}", port.name),
+	    };
+	    
+	    assert_eq!(info.name, port.name);
+	    assert_eq!(info.kind, Some(kind));
+	    assert_eq!(info.lower_bound(SAMPLE_RATE), lower_bound(port));
+	    assert_eq!(info.upper_bound(SAMPLE_RATE), upper_bound(port));
+	    if kind == PortKind::ControlInput {
+		assert_eq!(info.default_value(SAMPLE_RATE).unwrap_or(0.0),
+			   default_value(port), "{}", port.name);
+	    }
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Going through the C ABI must not change the output compared to
+// calling the `Plugin` implementation directly.
+#[test]
+fn renders_like_plugin_trait() {
+    let library = library();
+    let input = test_signal(1.0);
+    
+    for (index, plugin) in library.plugins().iter().enumerate() {
+	let desc = descriptor(index as u64);
+	let controls = plugin.controls(SAMPLE_RATE, &[]).unwrap();
+	
+	for &block_size in [1, 64, 1000].iter() {
+	    let expected = common::render(&desc, &controls, (&input.0, &input.1),
+					  block_size);
+	    let output = plugin.render(SAMPLE_RATE, &[input.0.clone(), input.1.clone()],
+				       &controls, block_size).unwrap();
+	    
+	    assert_eq!(output, vec![expected.0, expected.1],
+		       "{} with blocks of {}", plugin.label(), block_size);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn impulse_is_delayed() {
+    let library = library();
+    let plugin = library.plugin("rust_delay_5s_stereo").unwrap();
+    let controls = plugin.controls(SAMPLE_RATE, &[
+	("Left Delay (seconds)", 0.1),
+	("Right Delay (seconds)", 0.2),
+	("Left Dry/Wet", 1.0),
+	("Right Dry/Wet", 1.0),
+	("Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)", 0.0),
+    ]).unwrap();
+    let input = impulse(0.5);
+    
+    let output = plugin.render(SAMPLE_RATE, &[input.0, input.1], &controls, 256)
+	.unwrap();
+    
+    for (channel, delay) in output.iter().zip([0.1, 0.2].iter()) {
+	let echo = (delay * SAMPLE_RATE as Data) as usize;
+	
+	for (ii, &x) in channel.iter().enumerate() {
+	    assert_eq!(x, if ii == echo { 1.0 } else { 0.0 }, "sample {}", ii);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// A host may deactivate and reactivate an instance, which has to
+// start over from silence.
+#[test]
+fn activate_resets_instance() {
+    let library = library();
+    let plugin = library.plugin("rust_delay_5s_stereo").unwrap();
+    let mut controls = plugin.controls(SAMPLE_RATE, &[]).unwrap();
+    let input = test_signal(0.5);
+    let mut first = (vec![0.0; input.0.len()], vec![0.0; input.1.len()]);
+    let mut second = first.clone();
+    
+    let mut instance = plugin.instantiate(SAMPLE_RATE).unwrap();
+    
+    for output in [&mut first, &mut second].iter_mut() {
+	let mut input = input.clone();
+	let mut control_in = controls.iter_mut();
+	
+	unsafe {
+	    for port in plugin.ports() {
+		let location: *mut Data = match (port.kind.unwrap(), port.index % 2) {
+		    (PortKind::AudioInput, 0) => input.0.as_mut_ptr(),
+		    (PortKind::AudioInput, _) => input.1.as_mut_ptr(),
+		    (PortKind::AudioOutput, 0) => output.0.as_mut_ptr(),
+		    (PortKind::AudioOutput, _) => output.1.as_mut_ptr(),
+		    (PortKind::ControlInput, _) => control_in.next().unwrap(),
+		    (PortKind::ControlOutput, _) => panic!("unexpected control output"),
+		};
+		instance.connect_port(port.index, location);
+	    }
+	    
+	    instance.activate();
+	    instance.run(input.0.len());
+	    instance.deactivate();
+	}
+    }
+    
+    assert!(common::peak(&first.0) > 0.0);
+    assert_eq!(first, second);
+}
+
+// -------------------------------------------------------------------
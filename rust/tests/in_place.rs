@@ -0,0 +1,43 @@
+// -------------------------------------------------------------------
+// Hosts may connect an audio input and an audio output to the same
+// buffer unless the plugin declares PROP_INPLACE_BROKEN. The ladspa
+// crate passes them to run() as a shared and a mutable slice, which
+// must not alias, so all plugins have to declare it. Running them
+// in-place is undefined behaviour and hence not tested.
+// -------------------------------------------------------------------
+
+extern crate ladspa;
+extern crate rust_delay_5s_stereo;
+
+use ladspa::ffi::{ladspa_descriptor, ladspa_h};
+use ladspa::PluginDescriptor;
+use rust_delay_5s_stereo::get_ladspa_descriptor;
+
+// -------------------------------------------------------------------
+
+fn all_plugins() -> Vec<PluginDescriptor> {
+    (0..).map(get_ladspa_descriptor)
+	.take_while(|desc| desc.is_some())
+	.map(|desc| desc.unwrap())
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+// The properties have to reach the host through the exported C
+// interface.
+#[test]
+fn plugins_declare_in_place_broken() {
+    let plugins = all_plugins();
+    assert!(plugins.iter().any(|desc| desc.label == "rust_multi_tap_5s_stereo"));
+    
+    for (index, desc) in plugins.iter().enumerate() {
+	let ffi = unsafe { &*ladspa_descriptor(index as _) };
+	
+	assert_eq!(ffi.unique_id, desc.unique_id as _);
+	assert_ne!(ffi.properties & ladspa_h::PROPERTY_INPLACE_BROKEN, 0, "{}", desc.label);
+	assert_ne!(ffi.properties & ladspa_h::PROPERTY_HARD_RT_CAPABLE, 0, "{}", desc.label);
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,122 @@
+// -------------------------------------------------------------------
+// Reading the delay line at fractional positions.
+//
+// The delays are a whole number of samples plus a fraction. The
+// output is compared with the input evaluated at the exact position
+// the read head is at.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{render_with, samples, SAMPLE_RATE, WET, INTERPOLATION};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const NONE: Data = 0.0;
+const LINEAR: Data = 1.0;
+const CUBIC: Data = 2.0;
+const ALLPASS: Data = 3.0;
+
+// Whole samples of the delays.
+const WHOLE: f64 = 100.0;
+const FRACTIONS: [f64; 2] = [0.25, 0.5];
+
+// Settling time of the allpass, which starts from silence.
+const SETTLE: Data = 0.01;
+
+// -------------------------------------------------------------------
+
+// Renders `signal(t)`, a function of the time in samples, through the
+// wet delay and returns the left output together with the delay (in
+// samples) the read head is actually at. The delay control is given
+// in seconds and single precision, so it is off by a tiny bit.
+fn render_signal<F>(signal: F, delay: f64, interpolation: Data) -> (Vec<Data>, f64)
+where F: Fn(f64) -> f64 {
+    let input: Vec<Data> = (0..samples(0.1)).map(|ii| signal(ii as f64) as Data).collect();
+    let seconds = (delay / SAMPLE_RATE as f64) as Data;
+    let output = render_with(&WET, &[("Left Delay (seconds)", seconds),
+				     ("Right Delay (seconds)", seconds),
+				     (INTERPOLATION, interpolation)],
+			     (&input, &input));
+    
+    (output.0, seconds as f64 * SAMPLE_RATE as f64)
+}
+
+// Largest deviation of the output from the delayed signal, leaving
+// out the beginning.
+fn error<F>(signal: F, delay: f64, interpolation: Data) -> f64
+where F: Fn(f64) -> f64 {
+    let (output, delay) = render_signal(&signal, delay, interpolation);
+    let delay = if interpolation == NONE { delay.floor() } else { delay };
+    
+    output.iter()
+	.enumerate()
+	.skip(samples(SETTLE))
+	.map(|(ii, &y)| (y as f64 - signal(ii as f64 - delay)).abs())
+	.fold(0.0, f64::max)
+}
+
+// -------------------------------------------------------------------
+
+// A ramp rising by 1 per sample, scaled down to keep the precision of
+// single floats. Each output sample tells the position it was read at.
+fn ramp(t: f64) -> f64 {
+    if t < 0.0 { 0.0 } else { 1e-3 * t }
+}
+
+// A 1 kHz sine.
+fn sine(t: f64) -> f64 {
+    if t < 0.0 {
+	0.0
+    } else {
+	(2.0 * std::f64::consts::PI * 1000.0 * t / SAMPLE_RATE as f64).sin()
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Every mode reads a ramp exactly at the position of its read head,
+// none at the truncated one.
+#[test]
+fn ramp_is_read_at_fractional_position() {
+    for &fraction in FRACTIONS.iter() {
+	for &mode in [NONE, LINEAR, CUBIC, ALLPASS].iter() {
+	    // In samples of the ramp.
+	    let error = error(ramp, WHOLE + fraction, mode) / 1e-3;
+	    
+	    assert!(error < 1e-3, "mode {}, fraction {}: off by {} samples",
+		    mode, fraction, error);
+	}
+    }
+}
+
+// Without interpolation the delay is truncated, so the echo is a
+// fraction of a sample early.
+#[test]
+fn none_truncates_delay() {
+    for &fraction in FRACTIONS.iter() {
+	let (output, _) = render_signal(ramp, WHOLE + fraction, NONE);
+	let ii = samples(SETTLE);
+	
+	assert_eq!(output[ii], ramp(ii as f64 - WHOLE) as Data);
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The modes differ in how well they follow curved signals. The limits
+// are a bit above the errors of the respective interpolators at
+// 1 kHz.
+#[test]
+fn sine_is_read_at_fractional_position() {
+    for &fraction in FRACTIONS.iter() {
+	for &(mode, limit) in [(LINEAR, 3e-3), (CUBIC, 1e-4), (ALLPASS, 3e-4)].iter() {
+	    let error = error(sine, WHOLE + fraction, mode);
+	    
+	    assert!(error < limit, "mode {}, fraction {}: error {}", mode, fraction, error);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
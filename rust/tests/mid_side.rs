@@ -0,0 +1,121 @@
+// -------------------------------------------------------------------
+// Mid/side processing and the stereo width of the delayed signal.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{echoes, impulse, render_with, samples, test_signal, WET, WET_DELAY};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const LEFT_DELAY: Data = WET_DELAY;
+const RIGHT_DELAY: Data = 0.2;
+
+const MODE: &str = "Stereo Mode (0: left/right, 1: mid/side)";
+
+// -------------------------------------------------------------------
+
+// Renders the input through the wet delay, the right channel (or the
+// side signal) repeating later than the left one.
+fn render_wet(changes: &[(&str, Data)], input: (&[Data], &[Data]))
+	      -> (Vec<Data>, Vec<Data>) {
+    render_with(&WET, &[&[("Right Delay (seconds)", RIGHT_DELAY)], changes].concat(),
+		input)
+}
+
+// -------------------------------------------------------------------
+
+// An impulse on the left channel only consists of equal parts of mid
+// and side, which are delayed by the left and right delay
+// respectively.
+#[test]
+fn mid_and_side_are_delayed_separately() {
+    let mut input = impulse(0.5);
+    input.1[0] = 0.0;
+    let output = render_wet(&[(MODE, 1.0)], (&input.0, &input.1));
+    
+    assert_eq!(echoes(&output.0), vec![(samples(LEFT_DELAY), 0.5),
+				       (samples(RIGHT_DELAY), 0.5)]);
+    assert_eq!(echoes(&output.1), vec![(samples(LEFT_DELAY), 0.5),
+				       (samples(RIGHT_DELAY), -0.5)]);
+}
+
+// Whatever is done to the side signal, mono input stays mono.
+#[test]
+fn mono_input_stays_mono() {
+    let input = test_signal(1.0).0;
+    let output = render_wet(&[(MODE, 1.0),
+			       ("Right Feedback", 0.7),
+			       ("Stereo Width", 2.0)], (&input, &input));
+    
+    assert_eq!(output.0, output.1);
+}
+
+#[test]
+fn width_scales_difference_of_echoes() {
+    let input = impulse(0.5);
+    
+    let mono = render_wet(&[("Stereo Width", 0.0)], (&input.0, &input.1));
+    for channel in [&mono.0, &mono.1].iter() {
+	assert_eq!(echoes(channel), vec![(samples(LEFT_DELAY), 0.5),
+					 (samples(RIGHT_DELAY), 0.5)]);
+    }
+    
+    let wide = render_wet(&[("Stereo Width", 2.0)], (&input.0, &input.1));
+    assert_eq!(echoes(&wide.0), vec![(samples(LEFT_DELAY), 1.5),
+				     (samples(RIGHT_DELAY), -0.5)]);
+    assert_eq!(echoes(&wide.1), vec![(samples(LEFT_DELAY), -0.5),
+				     (samples(RIGHT_DELAY), 1.5)]);
+}
+
+// The width only applies to the output, the repeats keep their level.
+#[test]
+fn width_does_not_affect_feedback() {
+    let mut input = impulse(0.5);
+    input.1[0] = 0.0;
+    let output = render_wet(&[("Stereo Width", 0.0), ("Left Feedback", 0.5)],
+			    (&input.0, &input.1));
+    
+    assert_eq!(echoes(&output.0)[..2], [(samples(LEFT_DELAY), 0.5),
+					(samples(2.0 * LEFT_DELAY), 0.25)]);
+}
+
+// Mid and side of an input on a single channel are only half as loud,
+// which must not keep the echoes from being ducked.
+#[test]
+fn ducking_follows_left_and_right_input() {
+    let mut input = test_signal(1.0);
+    input.1 = vec![0.0; input.1.len()];
+    let render_mode = |mode| render_wet(&[
+	(MODE, mode),
+	("Right Delay (seconds)", LEFT_DELAY),
+	("Ducking Threshold (dB)", -20.0),
+	("Ducking Amount (dB)", 40.0),
+    ], (&input.0, &input.1));
+    let left_right = render_mode(0.0);
+    let mid_side = render_mode(1.0);
+    
+    for (output, reference) in [(&mid_side.0, &left_right.0),
+				(&mid_side.1, &left_right.1)].iter() {
+	for (x, y) in output.iter().zip(reference.iter()) {
+	    assert!((x - y).abs() < 1e-6, "{} instead of {}", x, y);
+	}
+    }
+}
+
+#[test]
+fn dry_signal_passes_mid_side_unchanged() {
+    let input = test_signal(0.5);
+    let output = render_wet(&[(MODE, 1.0),
+			       ("Left Dry/Wet", 0.0),
+			       ("Right Dry/Wet", 0.0)], (&input.0, &input.1));
+    
+    for (output, input) in [(&output.0, &input.0), (&output.1, &input.1)].iter() {
+	for (x, y) in output.iter().zip(input.iter()) {
+	    assert!((x - y).abs() < 1e-6);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
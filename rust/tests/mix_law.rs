@@ -0,0 +1,96 @@
+// -------------------------------------------------------------------
+// The laws combining the input with the delayed signal.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{impulse, render_with, samples, Fixture, INTERPOLATION};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const DELAY: Data = 0.1;
+
+const MIX_LAW: &str = "Mix Law (0: linear, 1: equal-power, 2: separate levels)";
+
+// The delay without interpolation, mixed by the default law.
+const DELAYED: Fixture = Fixture {
+    index: 0,
+    controls: &[
+	("Left Delay (seconds)", DELAY),
+	("Right Delay (seconds)", DELAY),
+	(INTERPOLATION, 0.0),
+    ],
+};
+
+// -------------------------------------------------------------------
+
+// Renders an impulse and returns the gain of the dry signal and of
+// the echo on both channels.
+fn gains(changes: &[(&str, Data)]) -> [(Data, Data); 2] {
+    let input = impulse(2.0 * DELAY);
+    let output = render_with(&DELAYED, changes, (&input.0, &input.1));
+    let echo = samples(DELAY);
+    
+    for channel in [&output.0, &output.1].iter() {
+	assert!(channel.iter().enumerate().all(|(ii, &x)| ii == 0 || ii == echo || x == 0.0));
+    }
+    
+    [(output.0[0], output.0[echo]), (output.1[0], output.1[echo])]
+}
+
+fn decibel(x: Data) -> Data {
+    (10.0 as Data).powf(x / 20.0)
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn linear_law_sums_to_unity() {
+    for &dry_wet in [0.0, 0.25, 0.5, 1.0].iter() {
+	let [(dry, wet), _] = gains(&[("Left Dry/Wet", dry_wet)]);
+	
+	assert_eq!((dry, wet), (1.0 - dry_wet, dry_wet));
+    }
+}
+
+#[test]
+fn equal_power_law_keeps_level_constant() {
+    for &dry_wet in [0.1, 0.25, 0.5, 0.9].iter() {
+	let [(dry, wet), _] = gains(&[(MIX_LAW, 1.0), ("Left Dry/Wet", dry_wet)]);
+	
+	assert!((dry * dry + wet * wet - 1.0).abs() < 1e-6, "{}: {} {}", dry_wet, dry, wet);
+    }
+    
+    let [(dry, wet), _] = gains(&[(MIX_LAW, 1.0), ("Left Dry/Wet", 0.5)]);
+    assert_eq!(dry, wet);
+}
+
+#[test]
+fn equal_power_law_is_exact_at_either_end() {
+    assert_eq!(gains(&[(MIX_LAW, 1.0), ("Left Dry/Wet", 0.0)])[0], (1.0, 0.0));
+    assert_eq!(gains(&[(MIX_LAW, 1.0), ("Left Dry/Wet", 1.0)])[0], (0.0, 1.0));
+}
+
+#[test]
+fn separate_levels_ignore_dry_wet() {
+    let gains = gains(&[(MIX_LAW, 2.0),
+			("Left Dry/Wet", 0.0),
+			("Right Dry/Wet", 1.0),
+			("Dry Level (dB)", -6.0),
+			("Wet Level (dB)", 6.0)]);
+    
+    for &(dry, wet) in gains.iter() {
+	assert!((dry - decibel(-6.0)).abs() < 1e-6, "{}", dry);
+	assert!((wet - decibel(6.0)).abs() < 1e-6, "{}", wet);
+    }
+}
+
+#[test]
+fn minimum_level_mutes() {
+    let gains = gains(&[(MIX_LAW, 2.0), ("Dry Level (dB)", -60.0)]);
+    
+    assert_eq!(gains[0], (0.0, 1.0));
+}
+
+// -------------------------------------------------------------------
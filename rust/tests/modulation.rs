@@ -0,0 +1,150 @@
+// -------------------------------------------------------------------
+// Modulation of the read head by the LFO.
+//
+// A ramp is used as input. With linear interpolation each output
+// sample then tells the exact position the read head was at.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{render_with, Fixture, INTERPOLATION, SAMPLE_RATE, WET};
+use ladspa::Data;
+use std::f32::consts::PI;
+
+// -------------------------------------------------------------------
+
+const DELAY: Data = 0.1;
+const DEPTH: Data = 0.005;
+const RATE: Data = 1.5;
+
+const WAVEFORM: &str = "Modulation Waveform (0: sine, 1: triangle, 2: random)";
+
+// Changes to the wet delay modulating it, with linear interpolation.
+const MODULATED: [(&str, Data); 3] = [
+    (INTERPOLATION, 1.0),
+    ("Modulation Rate (Hz)", RATE),
+    ("Modulation Depth (seconds)", DEPTH),
+];
+
+// -------------------------------------------------------------------
+
+fn ramp(seconds: Data) -> Vec<Data> {
+    (0..(seconds * SAMPLE_RATE as Data) as usize)
+	.map(|ii| ii as Data / SAMPLE_RATE as Data)
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+// Renders the ramp through the given fixture, modulated, and returns
+// the delay (in samples) the read head was at for every sample of both
+// channels. The first samples, for which the read head is still in
+// front of the ramp, are left out.
+fn read_positions(fixture: &Fixture, delay: Data, changes: &[(&str, Data)])
+		  -> (Vec<Data>, Vec<Data>) {
+    let input = ramp(delay + 2.0);
+    let changes = [&MODULATED[..],
+		   &[("Left Delay (seconds)", delay), ("Right Delay (seconds)", delay)],
+		   changes].concat();
+    let output = render_with(fixture, &changes, (&input, &input));
+    let start = ((delay + 2.0 * DEPTH) * SAMPLE_RATE as Data) as usize;
+    let positions = |output: &[Data]| -> Vec<Data> {
+	output.iter()
+	    .enumerate()
+	    .skip(start)
+	    .map(|(ii, x)| ii as Data - x * SAMPLE_RATE as Data)
+	    .collect()
+    };
+    
+    (positions(&output.0), positions(&output.1))
+}
+
+// -------------------------------------------------------------------
+
+// Compares the read positions against a waveform, given as a function
+// of the phase in periods.
+fn assert_waveform<F>(positions: &[Data], delay: Data, offset: Data, waveform: F)
+where F: Fn(Data) -> Data {
+    let start = ((delay + 2.0 * DEPTH) * SAMPLE_RATE as Data) as usize;
+    
+    for (ii, position) in positions.iter().enumerate() {
+	let time = (start + ii) as Data / SAMPLE_RATE as Data;
+	let expected = (delay + DEPTH * waveform(RATE * time + offset)) *
+	    SAMPLE_RATE as Data;
+	
+	assert!((position - expected).abs() < 0.1,
+		"read head at {} instead of {} after {} s", position, expected, time);
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn sine_moves_read_head() {
+    let positions = read_positions(&WET, DELAY, &[(WAVEFORM, 0.0)]);
+    let sine = |phase: Data| (2.0 * PI * phase).sin();
+    
+    assert_waveform(&positions.0, DELAY, 0.0, sine);
+    assert_waveform(&positions.1, DELAY, 0.0, sine);
+}
+
+#[test]
+fn triangle_moves_read_head() {
+    let positions = read_positions(&WET, DELAY, &[(WAVEFORM, 1.0)]);
+    let triangle = |phase: Data| {
+	let phase = phase - phase.floor();
+	
+	if phase < 0.25 {
+	    4.0 * phase
+	} else if phase < 0.75 {
+	    2.0 - 4.0 * phase
+	} else {
+	    4.0 * phase - 4.0
+	}
+    };
+    
+    assert_waveform(&positions.0, DELAY, 0.0, triangle);
+    assert_waveform(&positions.1, DELAY, 0.0, triangle);
+}
+
+#[test]
+fn random_moves_read_head_smoothly() {
+    let positions = read_positions(&WET, DELAY, &[(WAVEFORM, 2.0)]);
+    let center = DELAY * SAMPLE_RATE as Data;
+    let depth = DEPTH * SAMPLE_RATE as Data;
+    // Steepest slope of a cosine segment spanning the whole range
+    // within a period.
+    let max_step = PI * depth * RATE / SAMPLE_RATE as Data;
+    
+    assert_eq!(positions.0, positions.1);
+    assert!(positions.0.iter().all(|x| (x - center).abs() <= depth + 0.1));
+    assert!(positions.0.windows(2).all(|pair| (pair[1] - pair[0]).abs() <= max_step + 0.1));
+    assert!(positions.0.iter().any(|x| (x - center).abs() > 0.1 * depth));
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn stereo_phase_offsets_right_channel() {
+    let positions = read_positions(&WET, DELAY,
+				   &[("Modulation Stereo Phase (degrees)", 90.0)]);
+    let sine = |phase: Data| (2.0 * PI * phase).sin();
+    
+    assert_waveform(&positions.0, DELAY, 0.0, sine);
+    assert_waveform(&positions.1, DELAY, 0.25, sine);
+}
+
+// -------------------------------------------------------------------
+
+// The buffers leave room for modulating the longest delay.
+#[test]
+fn maximum_delay_can_be_modulated() {
+    let fixture = Fixture { index: 1, ..WET };
+    assert_eq!(fixture.descriptor().label, "rust_delay_1s_stereo");
+    
+    let positions = read_positions(&fixture, 1.0, &[]);
+    
+    assert_waveform(&positions.0, 1.0, 0.0, |phase| (2.0 * PI * phase).sin());
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,125 @@
+// -------------------------------------------------------------------
+// The multi-tap delay reading one shared delay line at several
+// positions.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{echoes, impulse, peak_between, render_with, samples, Fixture, INTERPOLATION};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const MULTI_TAP: Fixture = Fixture { index: 4, controls: &[] };
+
+// All taps muted, fully wet and without interpolation.
+const MUTED: Fixture = Fixture {
+    index: 4,
+    controls: &[
+	("Dry/Wet", 1.0),
+	(INTERPOLATION, 0.0),
+	("Tap 1 Gain", 0.0),
+    ],
+};
+
+// -------------------------------------------------------------------
+
+#[test]
+fn only_first_tap_is_audible_by_default() {
+    assert_eq!(MULTI_TAP.descriptor().label, "rust_multi_tap_5s_stereo");
+    let input = impulse(1.5);
+    let output = render_with(&MULTI_TAP, &[("Dry/Wet", 1.0)], (&input.0, &input.1));
+    let gain = (0.5 as Data).sqrt();
+    
+    for channel in [&output.0, &output.1].iter() {
+	let echoes = echoes(channel);
+	
+	assert!(echoes.iter().all(|&(ii, _)| (ii as isize - samples(1.0) as isize).abs() <= 1),
+		"{:?}", echoes);
+	let sum = echoes.iter().map(|&(_, x)| x).sum::<Data>();
+	assert!((sum - gain).abs() < 1e-6, "{}", sum);
+    }
+}
+
+#[test]
+fn taps_produce_pattern_of_echoes() {
+    let input = impulse(1.0);
+    let times = [0.1, 0.15, 0.2, 0.3, 0.35, 0.4, 0.6, 0.75];
+    let names = (1..=8)
+	.map(|tap| (format!("Tap {} Time (seconds)", tap),
+		    format!("Tap {} Gain", tap),
+		    format!("Tap {} Pan", tap)))
+	.collect::<Vec<_>>();
+    let mut changes = vec![];
+    for (ii, (time, gain, pan)) in names.iter().enumerate() {
+	changes.push((time.as_str(), times[ii]));
+	changes.push((gain.as_str(), 1.0 - 0.1 * ii as Data));
+	// Even taps to the left, odd ones to the right.
+	changes.push((pan.as_str(), if ii % 2 == 0 { -1.0 } else { 1.0 }));
+    }
+    let output = render_with(&MUTED, &changes, (&input.0, &input.1));
+    
+    for (ch, channel) in [&output.0, &output.1].iter().enumerate() {
+	let echoes = echoes(channel);
+	let expected = (0..8).filter(|ii| ii % 2 == ch).collect::<Vec<_>>();
+	
+	assert_eq!(echoes.len(), expected.len(), "{:?}", echoes);
+	for (&(position, value), &ii) in echoes.iter().zip(expected.iter()) {
+	    assert!((position as isize - samples(times[ii]) as isize).abs() <= 1,
+		    "tap {} at {}", ii + 1, position);
+	    assert!((value - (1.0 - 0.1 * ii as Data)).abs() < 1e-6,
+		    "tap {}: {}", ii + 1, value);
+	}
+    }
+}
+
+#[test]
+fn inputs_are_summed_into_single_line() {
+    let mut input = impulse(0.5);
+    input.0[0] = 0.0;
+    let output = render_with(&MUTED, &[
+	("Tap 1 Time (seconds)", 0.25),
+	("Tap 1 Gain", 1.0),
+	("Tap 1 Pan", -1.0),
+    ], (&input.0, &input.1));
+    
+    assert_eq!(echoes(&output.0), vec![(samples(0.25), 0.5)]);
+    assert!(echoes(&output.1).is_empty());
+}
+
+#[test]
+fn feedback_repeats_pattern() {
+    let input = impulse(1.0);
+    let output = render_with(&MUTED, &[
+	("Tap 1 Time (seconds)", 0.25),
+	("Tap 1 Gain", 1.0),
+	("Tap 1 Pan", -1.0),
+	("Feedback", 0.5),
+    ], (&input.0, &input.1));
+    
+    assert_eq!(echoes(&output.0), vec![(samples(0.25), 1.0),
+				       (samples(0.5), 0.5),
+				       (samples(0.75), 0.25)]);
+}
+
+// The loop gain is limited no matter how many taps are turned up.
+#[test]
+fn repeats_decay_with_all_taps_at_full_gain() {
+    let input = impulse(20.0);
+    let mut changes = vec![("Feedback", 0.99)];
+    let names = (1..=8)
+	.map(|tap| (format!("Tap {} Time (seconds)", tap), format!("Tap {} Gain", tap)))
+	.collect::<Vec<_>>();
+    for (ii, (time, gain)) in names.iter().enumerate() {
+	changes.push((time.as_str(), 0.05 * (ii + 1) as Data));
+	changes.push((gain.as_str(), 1.0));
+    }
+    let output = render_with(&MUTED, &changes, (&input.0, &input.1));
+    
+    for channel in [&output.0, &output.1].iter() {
+	assert!(channel.iter().all(|x| x.is_finite()));
+	assert!(peak_between(channel, 19.0, 20.0) < peak_between(channel, 0.0, 1.0));
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,106 @@
+// -------------------------------------------------------------------
+// Feedback between the channels and the ping-pong mode built on top
+// of it.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{echoes, impulse, render_with, samples, WET, WET_DELAY};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// The right channel repeats later than the left one, so the echoes of
+// both can be told apart.
+const LEFT_DELAY: Data = WET_DELAY;
+const RIGHT_DELAY: Data = 0.25;
+
+// -------------------------------------------------------------------
+
+// Renders an impulse on the given channels.
+fn render_impulse(left: bool, right: bool, changes: &[(&str, Data)])
+		  -> (Vec<Data>, Vec<Data>) {
+    let mut input = impulse(1.5);
+    if !left {
+	input.0[0] = 0.0;
+    }
+    if !right {
+	input.1[0] = 0.0;
+    }
+    
+    render_with(&WET, &[&[("Right Delay (seconds)", RIGHT_DELAY)], changes].concat(),
+		(&input.0, &input.1))
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn cross_feedback_passes_echoes_to_other_channel() {
+    let output = render_impulse(true, false, &[("Left to Right Feedback", 0.5)]);
+    
+    assert_eq!(echoes(&output.0), vec![(samples(LEFT_DELAY), 1.0)]);
+    assert_eq!(echoes(&output.1), vec![(samples(LEFT_DELAY + RIGHT_DELAY), 0.5)]);
+    
+    let output = render_impulse(false, true, &[("Right to Left Feedback", 0.5)]);
+    
+    assert_eq!(echoes(&output.0), vec![(samples(LEFT_DELAY + RIGHT_DELAY), 0.5)]);
+    assert_eq!(echoes(&output.1), vec![(samples(RIGHT_DELAY), 1.0)]);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn ping_pong_bounces_echoes_between_channels() {
+    let period = LEFT_DELAY + RIGHT_DELAY;
+    
+    // Mono input, so the echoes would coincide without ping-pong.
+    let output = render_impulse(true, true, &[("Left Feedback", 0.5),
+					      ("Right Feedback", 0.25),
+					      ("Right to Left Feedback", 0.9),
+					      ("Ping-Pong", 1.0)]);
+    
+    assert_eq!(echoes(&output.0)[..3], [(samples(LEFT_DELAY), 1.0),
+				      (samples(LEFT_DELAY + period), 0.125),
+				      (samples(LEFT_DELAY + 2.0 * period), 0.125 * 0.125)]);
+    assert_eq!(echoes(&output.1)[..3], [(samples(period), 0.5),
+				      (samples(period + period), 0.5 * 0.125),
+				      (samples(period + 2.0 * period), 0.5 * 0.125 * 0.125)]);
+}
+
+#[test]
+fn ping_pong_starts_on_left_for_any_input() {
+    for &(left, right) in [(true, false), (false, true)].iter() {
+	let output = render_impulse(left, right, &[("Ping-Pong", 1.0)]);
+	
+	assert_eq!(echoes(&output.0), vec![(samples(LEFT_DELAY), 0.5)]);
+	assert!(echoes(&output.1).is_empty());
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The delay lines are processed in chunks. Delays shorter than a chunk
+// still have to repeat every period, with each repeat fed back right
+// away.
+#[test]
+fn short_delays_repeat_within_chunk() {
+    let input = impulse(0.1);
+    let output = render_with(&WET, &[
+	("Left Delay (seconds)", 0.0001),
+	("Right Delay (seconds)", 0.0002),
+	("Left Feedback", 0.5),
+	("Right Feedback", 0.5),
+    ], (&input.0, &input.1));
+    
+    // 4.41 and 8.82 samples, truncated.
+    for &(channel, period) in [(&output.0, 4), (&output.1, 8)].iter() {
+	let expected: Vec<_> = (1..)
+	    .map(|kk| (kk * period, (0.5 as Data).powi(kk as i32 - 1)))
+	    .take_while(|&(ii, _)| ii < 100)
+	    .collect();
+	
+	assert_eq!(echoes(&channel[..100]), expected);
+    }
+}
+
+// -------------------------------------------------------------------
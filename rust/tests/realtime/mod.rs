@@ -0,0 +1,267 @@
+// -------------------------------------------------------------------
+// Support for checking the hard real-time requirements of LADSPA.
+//
+// Including this module installs a global allocator counting the heap
+// operations of every thread. In addition, the kernel's per-thread
+// accounting in /proc is used to detect I/O system calls and blocking
+// (e.g. waiting for a contended lock or sleeping), which both show up
+// as voluntary context switches.
+// -------------------------------------------------------------------
+
+#![allow(dead_code)]
+
+use ladspa::{Data, PluginDescriptor, PortConnection, PortData,
+	     PortDescriptor};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::fs;
+
+use crate::common::{default_value, lower_bound, upper_bound, SAMPLE_RATE};
+
+// -------------------------------------------------------------------
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static DEALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+	ALLOCATIONS.with(|count| count.set(count.get() + 1));
+	System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+	DEALLOCATIONS.with(|count| count.set(count.get() + 1));
+	System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// -------------------------------------------------------------------
+
+// Everything a hard real-time capable function is not allowed to do.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Violations {
+    pub allocations: usize,
+    pub deallocations: usize,
+    // Read and write system calls.
+    pub io_syscalls: u64,
+    pub voluntary_context_switches: u64,
+}
+
+impl Violations {
+    pub fn none(&self) -> bool {
+	*self == Violations::default()
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[derive(Copy, Clone, Default)]
+struct Snapshot {
+    allocations: usize,
+    deallocations: usize,
+    io_syscalls: u64,
+    voluntary_context_switches: u64,
+}
+
+impl Snapshot {
+
+    // ---------------------------------------------------------------
+
+    fn take() -> Snapshot {
+	let io = fs::read_to_string("/proc/thread-self/io")
+	    .unwrap_or_default();
+	let status = fs::read_to_string("/proc/thread-self/status")
+	    .unwrap_or_default();
+	
+	Snapshot {
+	    allocations: ALLOCATIONS.with(|count| count.get()),
+	    deallocations: DEALLOCATIONS.with(|count| count.get()),
+	    io_syscalls: field(&io, "syscr:") + field(&io, "syscw:"),
+	    voluntary_context_switches:
+	    field(&status, "voluntary_ctxt_switches:"),
+	}
+    }
+
+    // ---------------------------------------------------------------
+
+    fn since(&self, earlier: &Snapshot) -> Snapshot {
+	Snapshot {
+	    allocations: self.allocations - earlier.allocations,
+	    deallocations: self.deallocations - earlier.deallocations,
+	    io_syscalls: self.io_syscalls - earlier.io_syscalls,
+	    voluntary_context_switches: self.voluntary_context_switches -
+		earlier.voluntary_context_switches,
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+fn field(content: &str, name: &str) -> u64 {
+    content.lines()
+	.find(|line| line.starts_with(name))
+	.and_then(|line| line[name.len()..].trim().parse().ok())
+	.unwrap_or(0)
+}
+
+// -------------------------------------------------------------------
+
+// Runs `f` and reports everything it did a real-time function must
+// not do. Taking the snapshots itself does some I/O and allocates,
+// which is why an empty measurement is subtracted.
+pub fn check<F: FnOnce()>(f: F) -> Violations {
+    let start = Snapshot::take();
+    let reference = Snapshot::take();
+    f();
+    let end = Snapshot::take();
+    
+    let overhead = reference.since(&start);
+    let used = end.since(&reference);
+    
+    Violations {
+	allocations: used.allocations.saturating_sub(overhead.allocations),
+	deallocations: used.deallocations.saturating_sub(overhead.deallocations),
+	io_syscalls: used.io_syscalls.saturating_sub(overhead.io_syscalls),
+	voluntary_context_switches: used.voluntary_context_switches
+	    .saturating_sub(overhead.voluntary_context_switches),
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Port data owned by the checker, large enough for `max_block_size`.
+pub struct Buffers {
+    audio: Vec<Vec<Data>>,
+    controls: Vec<Data>,
+}
+
+impl Buffers {
+
+    // ---------------------------------------------------------------
+
+    // Control inputs are set using `control(port)`.
+    pub fn new<F>(desc: &PluginDescriptor, max_block_size: usize,
+		  control: F) -> Buffers
+    where F: Fn(&ladspa::Port) -> Data {
+	let audio = desc.ports.iter()
+	    .filter(|port| matches!(port.desc, PortDescriptor::AudioInput |
+				    PortDescriptor::AudioOutput))
+	    .enumerate()
+	    .map(|(ii, _)| (0..max_block_size)
+		 .map(|jj| ((ii * 7 + jj) as Data * 0.01).sin())
+		 .collect())
+	    .collect();
+	let controls = desc.ports.iter()
+	    .map(|port| match port.desc {
+		PortDescriptor::ControlInput => control(port),
+		_ => 0.0,
+	    })
+	    .collect();
+	
+	Buffers { audio, controls }
+    }
+
+    // ---------------------------------------------------------------
+
+    // Connects every port of the plugin to the first `block_size`
+    // samples of the buffers.
+    pub fn connect<'a>(&'a mut self, desc: &PluginDescriptor,
+		       block_size: usize) -> Vec<PortConnection<'a>> {
+	let mut audio = self.audio.iter_mut();
+	
+	desc.ports.iter()
+	    .zip(self.controls.iter_mut())
+	    .map(|(port, control)| PortConnection {
+		port: *port,
+		data: match port.desc {
+		    PortDescriptor::AudioInput => PortData::AudioInput(
+			&audio.next().unwrap()[..block_size]),
+		    PortDescriptor::AudioOutput => PortData::AudioOutput(
+			RefCell::new(&mut audio.next().unwrap()[..block_size])),
+		    PortDescriptor::ControlInput =>
+			PortData::ControlInput(control),
+		    PortDescriptor::ControlOutput =>
+			PortData::ControlOutput(RefCell::new(control)),
+		    PortDescriptor::Invalid =>
+			panic!("invalid port {}", port.name),
+		},
+	    })
+	    .collect()
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Block sizes used to drive the plugins. Besides typical host
+// settings this includes odd sizes and sizes around the chunks used
+// internally.
+pub const BLOCK_SIZES: [usize; 14] =
+    [1, 2, 3, 7, 31, 63, 64, 65, 128, 441, 512, 1000, 1024, 8192];
+
+// -------------------------------------------------------------------
+
+// Instantiates the plugin with all controls set by `control` and
+// checks activate() as well as run() for each of the `BLOCK_SIZES`.
+// Afterwards it keeps running blocks of the largest size until at
+// least `seconds` of audio have been processed in total. Returns a
+// description of the first violation found.
+pub fn check_plugin<F>(desc: &PluginDescriptor, control: F, seconds: Data)
+		       -> Result<(), String>
+where F: Fn(&ladspa::Port) -> Data {
+    let max_block_size = *BLOCK_SIZES.iter().max().unwrap();
+    let mut buffers = Buffers::new(desc, max_block_size, control);
+    let mut plugin = (desc.new)(desc, SAMPLE_RATE);
+    
+    let violations = check(|| plugin.activate());
+    if !violations.none() {
+	return Err(format!("{}: activate(): {:?}", desc.label, violations));
+    }
+    
+    // A couple of blocks of each size, so every size also continues
+    // where the previous call left off.
+    let mut blocks = BLOCK_SIZES.iter().flat_map(|&block_size| vec![block_size; 4])
+	.collect::<Vec<_>>();
+    let processed = blocks.iter().sum::<usize>();
+    let total = (seconds * SAMPLE_RATE as Data) as usize;
+    if total > processed {
+	let count = (total - processed).div_ceil(max_block_size);
+	blocks.extend(vec![max_block_size; count]);
+    }
+    
+    for &block_size in blocks.iter() {
+	let connections = buffers.connect(desc, block_size);
+	let ports: Vec<&PortConnection> = connections.iter().collect();
+	
+	let violations = check(|| plugin.run(block_size, &ports));
+	if !violations.none() {
+	    return Err(format!("{}: run({}): {:?}", desc.label,
+			       block_size, violations));
+	}
+    }
+    
+    Ok(())
+}
+
+// -------------------------------------------------------------------
+
+// Value of a control port, either the default or one of its bounds.
+pub fn default_control(port: &ladspa::Port) -> Data {
+    default_value(port)
+}
+
+pub fn lower_control(port: &ladspa::Port) -> Data {
+    lower_bound(port).unwrap_or(0.0)
+}
+
+pub fn upper_control(port: &ladspa::Port) -> Data {
+    upper_bound(port).unwrap_or(1.0)
+}
+
+// -------------------------------------------------------------------
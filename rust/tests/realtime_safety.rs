@@ -0,0 +1,104 @@
+// -------------------------------------------------------------------
+// Every plugin exported by this library has to be hard real-time
+// capable: neither activate() nor run() may touch the heap, do I/O,
+// or block.
+// -------------------------------------------------------------------
+
+extern crate ladspa;
+extern crate rust_delay_5s_stereo;
+
+mod common;
+mod realtime;
+
+use ladspa::{Data, Port, PluginDescriptor};
+use realtime::{check_plugin, default_control, lower_control, upper_control};
+use rust_delay_5s_stereo::get_ladspa_descriptor;
+
+// -------------------------------------------------------------------
+
+fn all_plugins() -> Vec<PluginDescriptor> {
+    (0..).map(get_ladspa_descriptor)
+	.take_while(|desc| desc.is_some())
+	.map(|desc| desc.unwrap())
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+fn check_all_plugins(control: fn(&Port) -> Data) {
+    let plugins = all_plugins();
+    assert!(!plugins.is_empty());
+    
+    for desc in plugins.iter() {
+	if let Err(violation) = check_plugin(desc, control, 0.0) {
+	    panic!("{}", violation);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn plugins_are_realtime_safe_with_default_controls() {
+    check_all_plugins(default_control);
+}
+
+#[test]
+fn plugins_are_realtime_safe_with_lower_bounds() {
+    check_all_plugins(lower_control);
+}
+
+#[test]
+fn plugins_are_realtime_safe_with_upper_bounds() {
+    check_all_plugins(upper_control);
+}
+
+// The buffers of the 1 s variant hold less than two seconds, so its
+// delay lines wrap around within three seconds. The longer variants
+// would take ages to get there in a debug build.
+#[test]
+fn delay_lines_wrap_around_realtime_safely() {
+    let desc = all_plugins().into_iter()
+	.find(|desc| desc.label == "rust_delay_1s_stereo")
+	.unwrap();
+    let controls: [fn(&Port) -> Data; 3] = [default_control, lower_control, upper_control];
+    
+    for &control in controls.iter() {
+	if let Err(violation) = check_plugin(&desc, control, 3.0) {
+	    panic!("{}", violation);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn checker_detects_allocations() {
+    let violations = realtime::check(|| {
+	let buf: Vec<Data> = Vec::with_capacity(64);
+	drop(buf);
+    });
+    
+    assert_eq!(violations.allocations, 1);
+    assert_eq!(violations.deallocations, 1);
+}
+
+#[test]
+fn checker_detects_io() {
+    let violations = realtime::check(|| {
+	let _ = std::fs::read("/proc/self/stat");
+    });
+    
+    assert!(violations.io_syscalls > 0);
+}
+
+#[test]
+fn checker_detects_blocking() {
+    let violations = realtime::check(|| {
+	std::thread::sleep(std::time::Duration::from_millis(1));
+    });
+    
+    assert!(violations.voluntary_context_switches > 0);
+}
+
+// -------------------------------------------------------------------
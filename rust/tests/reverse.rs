@@ -0,0 +1,137 @@
+// -------------------------------------------------------------------
+// Playing each window of the delay time backwards.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{render_with, samples, step_after_change, Fixture, INTERPOLATION, WET, WET_DELAY};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const DELAY: Data = WET_DELAY;
+
+// Deviation allowed for the gain of the echoes. The grains do not hit
+// the impulses exactly at the center of their windows.
+const TOLERANCE: Data = 1e-3;
+
+// Largest difference between neighbouring samples when switching or
+// changing the delay time, twice the one of the sine.
+const MAX_STEP: Data = 0.032;
+
+const DIRECTION: &str = "Direction (0: forward, 1: reverse up to half the max delay)";
+
+// -------------------------------------------------------------------
+
+// Renders impulses given as (time, value) in seconds through the fully
+// wet, reversed delay.
+fn render_impulses(index: u64, delay: Data, impulses: &[(Data, Data)], seconds: Data)
+		   -> Vec<Data> {
+    let mut input = vec![0.0; samples(seconds)];
+    for &(time, value) in impulses {
+	input[samples(time)] = value;
+    }
+    let output = render_with(&Fixture { index, ..WET }, &[
+	("Left Delay (seconds)", delay),
+	("Right Delay (seconds)", delay),
+	(DIRECTION, 1.0),
+    ], (&input, &input));
+    
+    assert_eq!(output.0, output.1);
+    output.0
+}
+
+// -------------------------------------------------------------------
+
+// Sum of the output within two samples of the given time.
+fn echo(output: &[Data], seconds: Data) -> Data {
+    let center = samples(seconds);
+    
+    output[center - 2..=center + 2].iter().sum()
+}
+
+fn assert_echo(output: &[Data], seconds: Data, expected: Data) {
+    let value = echo(output, seconds);
+    
+    assert!((value - expected).abs() < TOLERANCE,
+	    "echo at {} s: {} instead of {}", seconds, value, expected);
+}
+
+// Gain of the grain reading an impulse `position` (relative to the
+// delay) into its window.
+fn hann(position: Data) -> Data {
+    (std::f32::consts::PI * position).sin().powi(2)
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn impulse_at_window_start_echoes_after_delay() {
+    let output = render_impulses(0, DELAY, &[(0.0, 1.0)], 0.5);
+    
+    assert_echo(&output, DELAY, 1.0);
+    let total: Data = output.iter().map(|x| x.abs()).sum();
+    assert!((total - 1.0).abs() < TOLERANCE, "{}", total);
+}
+
+// Within a window later input is played first. Each impulse is read by
+// both grains, each at its own time.
+#[test]
+fn window_is_played_backwards() {
+    let output = render_impulses(0, DELAY, &[(0.1 * DELAY, 1.0), (0.2 * DELAY, 0.5)], 0.5);
+    
+    assert_echo(&output, 0.8 * DELAY, 0.5 * hann(0.7));
+    assert_echo(&output, 0.9 * DELAY, hann(0.6));
+    assert_echo(&output, 1.8 * DELAY, 0.5 * hann(0.2));
+    assert_echo(&output, 1.9 * DELAY, hann(0.1));
+}
+
+// Played backwards, a window reaches twice its length into the past,
+// so it is limited to half the maximum delay.
+#[test]
+fn window_is_limited_to_half_maximum_delay() {
+    assert_eq!(Fixture { index: 1, ..WET }.descriptor().label, "rust_delay_1s_stereo");
+    let output = render_impulses(1, 1.0, &[(0.25, 1.0)], 2.0);
+    
+    assert_echo(&output, 0.75, hann(0.5));
+    assert_eq!(output, render_impulses(1, 0.5, &[(0.25, 1.0)], 2.0));
+}
+
+// -------------------------------------------------------------------
+
+// The switch is in the middle of a window, where the grains read far
+// from the delay time. The delay is interpolated, so the read heads
+// glide without any jumps.
+fn step_at_window_center(before: &[(&str, Data)], after: &[(&str, Data)]) -> Data {
+    let linear = (INTERPOLATION, 1.0);
+    
+    step_after_change(&WET, &[&[linear], before].concat(), &[&[linear], after].concat(),
+		      samples(0.53), samples(0.01))
+}
+
+#[test]
+fn switching_direction_is_cross_faded() {
+    let forward = [(DIRECTION, 0.0)];
+    let reverse = [(DIRECTION, 1.0)];
+    
+    let step = step_at_window_center(&forward, &reverse);
+    assert!(step < MAX_STEP, "{}", step);
+    let step = step_at_window_center(&reverse, &forward);
+    assert!(step < MAX_STEP, "{}", step);
+}
+
+#[test]
+fn window_follows_delay_time_smoothly() {
+    let before = [(DIRECTION, 1.0)];
+    
+    for &delay in [0.12, 0.3].iter() {
+	let step = step_at_window_center(&before, &[
+	    (DIRECTION, 1.0),
+	    ("Left Delay (seconds)", delay),
+	    ("Right Delay (seconds)", delay),
+	]);
+	assert!(step < MAX_STEP, "{}: {}", delay, step);
+    }
+}
+
+// -------------------------------------------------------------------
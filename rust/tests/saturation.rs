@@ -0,0 +1,141 @@
+// -------------------------------------------------------------------
+// Saturation of the delayed signal.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{peak, peak_between, render_with, samples, test_signal, SAMPLE_RATE, WET};
+use ladspa::Data;
+use std::f32::consts::PI;
+
+// -------------------------------------------------------------------
+
+const SATURATION: &str = "Saturation (0: off, 1: tanh, 2: tube, 3: tape)";
+const CURVES: [Data; 3] = [1.0, 2.0, 3.0];
+
+// -------------------------------------------------------------------
+
+fn sine(frequency: Data, amplitude: Data, seconds: Data) -> Vec<Data> {
+    (0..(seconds * SAMPLE_RATE as Data) as usize)
+	.map(|ii| amplitude * (2.0 * PI * frequency * ii as Data / SAMPLE_RATE as Data).sin())
+	.collect()
+}
+
+// Renders the signal on both channels and returns the left output.
+fn render_wet(input: &[Data], changes: &[(&str, Data)]) -> Vec<Data> {
+    render_with(&WET, changes, (input, input)).0
+}
+
+// -------------------------------------------------------------------
+
+// Magnitude of a single frequency component (Goertzel algorithm).
+fn magnitude(signal: &[Data], frequency: Data) -> Data {
+    let omega = 2.0 * std::f64::consts::PI * frequency as f64 / SAMPLE_RATE as f64;
+    let (mut s1, mut s2) = (0.0, 0.0);
+    
+    for &x in signal {
+	let s0 = x as f64 + 2.0 * omega.cos() * s1 - s2;
+	s2 = s1;
+	s1 = s0;
+    }
+    
+    ((s1 * s1 + s2 * s2 - 2.0 * omega.cos() * s1 * s2).sqrt() / signal.len() as f64) as Data
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn drive_without_curve_changes_nothing() {
+    let input = test_signal(1.0);
+    
+    assert_eq!(render_wet(&input.0, &[("Saturation Drive (dB)", 24.0)]),
+	       render_wet(&input.0, &[]));
+}
+
+// -------------------------------------------------------------------
+
+// Quiet signals pass the curves almost unaltered and the echoes stay
+// in place despite the oversampling.
+#[test]
+fn quiet_signals_pass_unaltered() {
+    let input = sine(440.0, 0.001, 0.5);
+    let clean = render_wet(&input, &[]);
+    
+    for &curve in CURVES.iter() {
+	let output = render_wet(&input, &[(SATURATION, curve)]);
+	let error: Vec<Data> = output.iter().zip(clean.iter()).map(|(x, y)| x - y).collect();
+	
+	assert!(peak(&error) < 0.02 * peak(&clean), "curve {}: error {}", curve, peak(&error));
+    }
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn loud_repeats_are_limited() {
+    let input = sine(220.0, 1.0, 2.0);
+    
+    for &curve in CURVES.iter() {
+	for &drive in [0.0, 12.0, 24.0].iter() {
+	    let output = render_wet(&input, &[("Left Feedback", 0.99),
+					      (SATURATION, curve),
+					      ("Saturation Drive (dB)", drive)]);
+	    let gain = (10.0 as Data).powf(drive / 20.0);
+	    
+	    // The clean repeats would build up to several times the
+	    // input level.
+	    assert!(peak(&output) < 1.5 / gain, "curve {}, drive {}: peak {}",
+		    curve, drive, peak(&output));
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// None of the curves amplifies, so the repeats of a loud burst die
+// away with high feedback instead of settling on an offset.
+#[test]
+fn repeats_decay_with_high_feedback() {
+    let mut input = sine(220.0, 1.0, 5.0);
+    for x in input[samples(0.5)..].iter_mut() {
+	*x = 0.0;
+    }
+    
+    for &curve in CURVES.iter() {
+	for &drive in [0.0, 12.0, 24.0].iter() {
+	    let output = render_wet(&input, &[("Left Feedback", 0.99),
+					      (SATURATION, curve),
+					      ("Saturation Drive (dB)", drive)]);
+	    let tail = &output[samples(4.5)..];
+	    let mean = tail.iter().sum::<Data>() / tail.len() as Data;
+	    
+	    assert!(mean.abs() < 1e-3, "curve {}, drive {}: mean {}", curve, drive, mean);
+	    assert!(peak(tail) < peak_between(&output, 0.0, 0.5),
+		    "curve {}, drive {}: tail {}", curve, drive, peak(tail));
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The third harmonic of a 15 kHz tone folds back to 900 Hz unless the
+// curve is applied at a higher sample rate.
+#[test]
+fn oversampling_reduces_aliasing() {
+    let gain = (10.0 as Data).powf(24.0 / 20.0);
+    let input = sine(15000.0, 1.0, 0.5);
+    let output = render_wet(&input, &[(SATURATION, 1.0), ("Saturation Drive (dB)", 24.0)]);
+    let naive: Vec<Data> = input.iter().map(|x| (gain * x).tanh() / gain).collect();
+    
+    let window = |signal: &[Data], start: Data| -> Vec<Data> {
+	let start = (start * SAMPLE_RATE as Data) as usize;
+	signal[start..start + SAMPLE_RATE as usize / 4].to_vec()
+    };
+    let alias = magnitude(&window(&output, 0.2), 900.0);
+    let naive_alias = magnitude(&window(&naive, 0.1), 900.0);
+    
+    assert!(alias < 0.1 * naive_alias, "alias {} vs {} without oversampling",
+	    alias, naive_alias);
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,130 @@
+// -------------------------------------------------------------------
+// Changes of the controls between two blocks are smoothed instead of
+// being applied instantly, and large jumps in the delay time are
+// cross-faded.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{echo_positions, impulse, render_switching, samples, step_after_change, Fixture,
+	     INTERPOLATION};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+// Sample at which the controls change, right between two blocks.
+const SWITCH: usize = 100 * BLOCK_SIZE;
+const BLOCK_SIZE: usize = 256;
+
+// Largest difference between neighbouring samples of the smoothed
+// output. Twice the one of the test signal itself.
+const MAX_STEP: Data = 0.032;
+
+// -------------------------------------------------------------------
+
+// Smoothing the changes of the controls, or not.
+const SMOOTHED: Fixture = Fixture {
+    index: 0,
+    controls: &[
+	("Left Delay (seconds)", 0.102),
+	("Right Delay (seconds)", 0.102),
+	("Smoothing Time (seconds)", 0.05),
+    ],
+};
+const INSTANT: Fixture = Fixture {
+    index: 0,
+    controls: &[
+	("Left Delay (seconds)", 0.102),
+	("Right Delay (seconds)", 0.102),
+	("Smoothing Time (seconds)", 0.0),
+    ],
+};
+
+// -------------------------------------------------------------------
+
+// Asserts that the change is click-free, while the same change
+// without smoothing is not.
+fn assert_smoothed(before: &[(&str, Data)], after: &[(&str, Data)]) {
+    let smoothed = step_after_change(&SMOOTHED, before, after, SWITCH, BLOCK_SIZE);
+    let instant = step_after_change(&INSTANT, before, after, SWITCH, BLOCK_SIZE);
+    
+    assert!(smoothed < MAX_STEP, "{}", smoothed);
+    assert!(instant > 2.0 * MAX_STEP, "{}", instant);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn delay_time_changes_glide() {
+    assert_smoothed(&[("Left Dry/Wet", 1.0), ("Right Dry/Wet", 1.0)],
+		    &[("Left Dry/Wet", 1.0), ("Right Dry/Wet", 1.0),
+		      ("Left Delay (seconds)", 0.122), ("Right Delay (seconds)", 0.132)]);
+}
+
+#[test]
+fn dry_wet_changes_glide() {
+    assert_smoothed(&[("Left Dry/Wet", 0.0), ("Right Dry/Wet", 0.0)],
+		    &[("Left Dry/Wet", 1.0), ("Right Dry/Wet", 1.0)]);
+}
+
+#[test]
+fn feedback_changes_glide() {
+    assert_smoothed(&[("Left Dry/Wet", 1.0), ("Right Dry/Wet", 1.0)],
+		    &[("Left Dry/Wet", 1.0), ("Right Dry/Wet", 1.0),
+		      ("Left Feedback", 0.9), ("Right Feedback", 0.9)]);
+}
+
+// -------------------------------------------------------------------
+
+// Renders an impulse through the fully wet delay, changing the delay
+// time from `before` to `after` at 0.2 s, before the echo is due.
+fn render_jump(before: Data, after: Data, interpolation: Data) -> (Vec<Data>, Vec<Data>) {
+    let fixture = Fixture {
+	index: 0,
+	controls: &[
+	    ("Left Dry/Wet", 1.0),
+	    ("Right Dry/Wet", 1.0),
+	    ("Smoothing Time (seconds)", 0.2),
+	],
+    };
+    let settings = |delay: Data| fixture.controls(&[
+	("Left Delay (seconds)", delay),
+	("Right Delay (seconds)", delay),
+	(INTERPOLATION, interpolation),
+    ]);
+    let input = impulse(1.0);
+    
+    render_switching(&fixture.descriptor(), &settings(before), &settings(after),
+		     samples(0.2), (&input.0, &input.1), samples(0.01))
+}
+
+// -------------------------------------------------------------------
+
+// The head reading at the old delay time is faded out while the one
+// at the new time is faded in, so the echo shows up at both positions
+// but nowhere in between.
+#[test]
+fn large_jumps_are_cross_faded() {
+    let output = render_jump(0.3, 0.5, 0.0);
+    
+    for channel in [&output.0, &output.1].iter() {
+	assert_eq!(echo_positions(channel), vec![samples(0.3), samples(0.5)]);
+	assert!((channel[samples(0.3)] - 0.5).abs() < 1e-3, "{}", channel[samples(0.3)]);
+	assert_eq!(channel[samples(0.5)], 1.0);
+    }
+}
+
+#[test]
+fn small_changes_are_glided() {
+    let output = render_jump(0.3, 0.32, 1.0);
+    
+    for channel in [&output.0, &output.1].iter() {
+	let echoes = echo_positions(channel);
+	
+	assert!(!echoes.is_empty());
+	assert!(echoes.iter().all(|&ii| ii > samples(0.3) + 1 && ii < samples(0.32) - 1),
+		"{:?}", echoes);
+    }
+}
+
+// -------------------------------------------------------------------
@@ -0,0 +1,110 @@
+// -------------------------------------------------------------------
+// Delay times synced to the tempo.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{impulse, render_with, SAMPLE_RATE, WET};
+use ladspa::Data;
+
+// -------------------------------------------------------------------
+
+const LEFT_NOTE_VALUE: &str =
+    "Left Note Value (0: 1/1, 1: 1/2, 2: 1/4, 3: 1/8, 4: 1/16, 5: 1/32, 6: 1/64)";
+const RIGHT_NOTE_VALUE: &str =
+    "Right Note Value (0: 1/1, 1: 1/2, 2: 1/4, 3: 1/8, 4: 1/16, 5: 1/32, 6: 1/64)";
+const LEFT_NOTE_MODIFIER: &str = "Left Note Modifier (0: straight, 1: dotted, 2: triplet)";
+const RIGHT_NOTE_MODIFIER: &str = "Right Note Modifier (0: straight, 1: dotted, 2: triplet)";
+
+// -------------------------------------------------------------------
+
+fn render_impulse(seconds: Data, changes: &[(&str, Data)]) -> (Vec<Data>, Vec<Data>) {
+    let input = impulse(seconds);
+    
+    render_with(&WET, changes, (&input.0, &input.1))
+}
+
+// -------------------------------------------------------------------
+
+// Position of the first echo in seconds.
+fn echo(signal: &[Data]) -> Data {
+    let position = signal.iter().position(|&x| x != 0.0).expect("no echo");
+    
+    position as Data / SAMPLE_RATE as Data
+}
+
+// Without interpolation the delay is truncated to whole samples.
+fn assert_echo(signal: &[Data], expected: Data) {
+    assert!((echo(signal) - expected).abs() < 1.0 / SAMPLE_RATE as Data,
+	    "echo after {} s instead of {} s", echo(signal), expected);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn note_values_follow_tempo() {
+    // (tempo, note value, modifier, expected delay)
+    let cases = [
+	(120.0, 3.0, 0.0, 0.25),
+	(120.0, 2.0, 0.0, 0.5),
+	(120.0, 3.0, 1.0, 0.375),
+	(120.0, 2.0, 2.0, 1.0 / 3.0),
+	(90.0, 2.0, 0.0, 2.0 / 3.0),
+	(150.0, 4.0, 1.0, 0.15),
+	(60.0, 0.0, 0.0, 4.0),
+    ];
+    
+    for &(tempo, value, modifier, expected) in cases.iter() {
+	let output = render_impulse(4.5, &[("Tempo Sync", 1.0),
+					   ("Tempo (BPM)", tempo),
+					   (LEFT_NOTE_VALUE, value),
+					   (LEFT_NOTE_MODIFIER, modifier)]);
+	
+	assert_echo(&output.0, expected);
+    }
+}
+
+#[test]
+fn default_note_is_eighth_at_120_bpm() {
+    let output = render_impulse(1.0, &[("Tempo Sync", 1.0)]);
+    
+    assert_echo(&output.0, 0.25);
+    assert_echo(&output.1, 0.25);
+}
+
+#[test]
+fn channels_have_own_note_values() {
+    let output = render_impulse(1.0, &[("Tempo Sync", 1.0),
+				       (LEFT_NOTE_VALUE, 2.0),
+				       (RIGHT_NOTE_VALUE, 4.0),
+				       (RIGHT_NOTE_MODIFIER, 1.0)]);
+    
+    assert_echo(&output.0, 0.5);
+    assert_echo(&output.1, 0.1875);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn seconds_are_used_without_sync() {
+    let output = render_impulse(1.0, &[("Left Delay (seconds)", 0.1),
+				       ("Tempo (BPM)", 60.0),
+				       (LEFT_NOTE_VALUE, 2.0)]);
+    
+    assert_echo(&output.0, 0.1);
+}
+
+#[test]
+fn long_notes_are_limited_to_maximum_delay() {
+    // A whole note at 20 BPM lasts 12 seconds.
+    let synced = render_impulse(6.0, &[("Tempo Sync", 1.0),
+				       ("Tempo (BPM)", 20.0),
+				       (LEFT_NOTE_VALUE, 0.0),
+				       (RIGHT_NOTE_VALUE, 0.0)]);
+    let limited = render_impulse(6.0, &[("Left Delay (seconds)", 5.0),
+					("Right Delay (seconds)", 5.0)]);
+    
+    assert_eq!(synced, limited);
+}
+
+// -------------------------------------------------------------------
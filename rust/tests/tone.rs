@@ -0,0 +1,145 @@
+// -------------------------------------------------------------------
+// Filters shaping the tone of the repeats.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{connect, controls, descriptor, peak_between, render, render_with,
+	     test_signal, Fixture, INTERPOLATION, SAMPLE_RATE, WET};
+use ladspa::{Data, PortConnection};
+use std::f32::consts::PI;
+
+// -------------------------------------------------------------------
+
+const DELAY: Data = 0.25;
+const FEEDBACK: Data = 0.5;
+const REPEATS: usize = 3;
+
+// Changes to the wet delay letting the repeats pass the filters over
+// and over again.
+const REPEATED: [(&str, Data); 4] = [
+    ("Left Delay (seconds)", DELAY),
+    ("Right Delay (seconds)", DELAY),
+    ("Left Feedback", FEEDBACK),
+    ("Right Feedback", FEEDBACK),
+];
+
+// -------------------------------------------------------------------
+
+// Peaks of the first repeats of a short sine burst, relative to the
+// ones without any filtering.
+fn attenuation(frequency: Data, changes: &[(&str, Data)]) -> Vec<Data> {
+    let length = ((REPEATS as Data + 1.0) * DELAY * SAMPLE_RATE as Data) as usize;
+    let burst = (0.1 * SAMPLE_RATE as Data) as usize;
+    let input: Vec<Data> = (0..length)
+	.map(|ii| if ii < burst {
+	    (2.0 * PI * frequency * ii as Data / SAMPLE_RATE as Data).sin()
+	} else {
+	    0.0
+	})
+	.collect();
+    let peaks = |changes: &[(&str, Data)]| -> Vec<Data> {
+	let output = render_with(&WET, &[&REPEATED[..], changes].concat(), (&input, &input));
+	
+	// Leave out the beginning and end of each repeat, where the
+	// filters are still settling.
+	(1..=REPEATS)
+	    .map(|repeat| {
+		let start = repeat as Data * DELAY;
+		
+		peak_between(&output.0, start + 0.02, start + 0.08)
+	    })
+	    .collect()
+    };
+    
+    peaks(changes).iter()
+	.zip(peaks(&[]).iter())
+	.map(|(filtered, unfiltered)| filtered / unfiltered)
+	.collect()
+}
+
+// -------------------------------------------------------------------
+
+fn assert_decreasing(attenuation: &[Data], limit: Data) {
+    assert!(attenuation[0] < limit, "{:?}", attenuation);
+    assert!(attenuation.windows(2).all(|pair| pair[1] < 0.8 * pair[0]), "{:?}", attenuation);
+}
+
+fn assert_unaltered(attenuation: &[Data]) {
+    assert!(attenuation.iter().all(|&x| x > 0.9 && x <= 1.0), "{:?}", attenuation);
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn lowpass_darkens_repeats() {
+    let changes = [("Low-Pass Cutoff (Hz)", 1000.0)];
+    
+    assert_decreasing(&attenuation(8000.0, &changes), 0.5);
+    assert_unaltered(&attenuation(100.0, &changes));
+}
+
+#[test]
+fn highpass_thins_repeats() {
+    let changes = [("High-Pass Cutoff (Hz)", 1000.0)];
+    
+    assert_decreasing(&attenuation(100.0, &changes), 0.5);
+    assert_unaltered(&attenuation(8000.0, &changes));
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn filters_are_off_by_default() {
+    let fixture = Fixture { index: 0, controls: &[("Left Feedback", FEEDBACK)] };
+    let input = test_signal(1.0);
+    let render = |changes: &[(&str, Data)]| {
+	render_with(&fixture, changes, (&input.0, &input.1))
+    };
+    
+    // Without the filters each repeat is an exact copy of the input.
+    let output = render(&[("Left Dry/Wet", 1.0), (INTERPOLATION, 0.0)]);
+    let delay = SAMPLE_RATE as usize;
+    assert_eq!(output.0[..delay].iter().filter(|&&x| x != 0.0).count(), 0);
+    
+    assert_eq!(render(&[]),
+	       render(&[("Low-Pass Cutoff (Hz)", 0.5 * SAMPLE_RATE as Data),
+			("High-Pass Cutoff (Hz)", 0.0)]));
+}
+
+// -------------------------------------------------------------------
+
+// The filter state carries over from one block to the next but not
+// into the next activation.
+#[test]
+fn filter_state_persists_until_activate() {
+    let desc = descriptor(0);
+    let controls = controls(&desc, &[("Left Delay (seconds)", 0.01),
+				     ("Right Delay (seconds)", 0.01),
+				     ("Left Feedback", FEEDBACK),
+				     ("Low-Pass Cutoff (Hz)", 2000.0),
+				     ("High-Pass Cutoff (Hz)", 200.0)]);
+    let input = test_signal(0.2);
+    let input = (&input.0[..], &input.1[..]);
+    
+    let expected = render(&desc, &controls, input, input.0.len());
+    assert_eq!(render(&desc, &controls, input, 1), expected);
+    
+    let mut plugin = (desc.new)(&desc, SAMPLE_RATE);
+    for _ in 0..2 {
+	let mut output = (vec![0.0; input.0.len()], vec![0.0; input.1.len()]);
+	
+	plugin.activate();
+	{
+	    let connections = connect(&desc, &controls, input,
+				      (&mut output.0, &mut output.1));
+	    let ports: Vec<&PortConnection> = connections.iter().collect();
+	    plugin.run(input.0.len(), &ports);
+	}
+	plugin.deactivate();
+	
+	assert_eq!(output, expected);
+    }
+}
+
+// -------------------------------------------------------------------
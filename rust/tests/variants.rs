@@ -0,0 +1,106 @@
+// -------------------------------------------------------------------
+// The family of descriptors differing in their maximum delay.
+// -------------------------------------------------------------------
+
+mod common;
+
+use common::{controls, descriptor, impulse, render, SAMPLE_RATE};
+use ladspa::{Data, PluginDescriptor};
+use rust_delay_5s_stereo::get_ladspa_descriptor;
+
+// -------------------------------------------------------------------
+
+// (label, maximum delay in seconds)
+const VARIANTS: [(&str, Data); 4] = [
+    ("rust_delay_5s_stereo", 5.0),
+    ("rust_delay_1s_stereo", 1.0),
+    ("rust_delay_30s_stereo", 30.0),
+    ("rust_delay_120s_stereo", 120.0),
+];
+
+// -------------------------------------------------------------------
+
+fn find(label: &str) -> PluginDescriptor {
+    (0..)
+	.map(get_ladspa_descriptor)
+	.take_while(Option::is_some)
+	.flatten()
+	.find(|desc| desc.label == label)
+	.unwrap_or_else(|| panic!("no plugin labelled {}", label))
+}
+
+// -------------------------------------------------------------------
+
+#[test]
+fn first_descriptor_keeps_label_and_id() {
+    let desc = descriptor(0);
+    
+    assert_eq!(desc.label, "rust_delay_5s_stereo");
+    assert_eq!(desc.unique_id, 400);
+}
+
+#[test]
+fn delay_ports_are_bounded_by_maximum_delay() {
+    for &(label, max_delay) in VARIANTS.iter() {
+	let desc = find(label);
+	
+	for port in desc.ports.iter().filter(|port| port.name.contains("Delay (seconds)")) {
+	    assert_eq!(port.upper_bound, Some(max_delay), "{}: {}", label, port.name);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// The 120 s variant is left out to keep the test fast.
+#[test]
+fn delay_reaches_maximum_delay() {
+    for &(label, max_delay) in VARIANTS[..3].iter() {
+	let desc = find(label);
+	let input = impulse(max_delay + 0.1);
+	let output = render(&desc, &controls(&desc, &[
+	    ("Left Delay (seconds)", max_delay),
+	    ("Right Delay (seconds)", 2.0 * max_delay),
+	    ("Left Dry/Wet", 1.0),
+	    ("Right Dry/Wet", 1.0),
+	    ("Interpolation (0: none, 1: linear, 2: cubic, 3: allpass)", 0.0),
+	]), (&input.0, &input.1), 4096);
+	let echo = (max_delay * SAMPLE_RATE as Data) as usize;
+	
+	for channel in [&output.0, &output.1].iter() {
+	    assert_eq!(channel.iter().position(|&x| x != 0.0), Some(echo), "{}", label);
+	}
+    }
+}
+
+// -------------------------------------------------------------------
+
+// Single precision only resolves an eighth of a sample at this delay,
+// so the fraction of the delay time has to be kept in double precision
+// to be interpolated correctly.
+#[test]
+fn long_delays_keep_fraction_of_sample() {
+    let desc = find("rust_delay_30s_stereo");
+    // The smallest step of the control above 29 s.
+    let delay = 29.0 + (2.0 as Data).powi(-19);
+    let input = impulse(29.1);
+    let output = render(&desc, &controls(&desc, &[
+	("Left Delay (seconds)", delay),
+	("Right Delay (seconds)", delay),
+	("Left Dry/Wet", 1.0),
+	("Right Dry/Wet", 1.0),
+    ]), (&input.0, &input.1), 4096);
+    let position = delay as f64 * SAMPLE_RATE as f64;
+    let whole = position.floor() as usize;
+    let frac = (position - position.floor()) as Data;
+    
+    for channel in [&output.0, &output.1].iter() {
+	let echoes = (0..channel.len()).filter(|&ii| channel[ii] != 0.0).collect::<Vec<_>>();
+	
+	assert_eq!(echoes, vec![whole, whole + 1]);
+	assert!((channel[whole] - (1.0 - frac)).abs() < 1e-6, "{}", channel[whole]);
+	assert!((channel[whole + 1] - frac).abs() < 1e-6, "{}", channel[whole + 1]);
+    }
+}
+
+// -------------------------------------------------------------------